@@ -152,6 +152,12 @@ fn generate_ffi(static_link: bool, modern_sqlite: bool) {
                 API = api;
                 Ok(())
             }
+            /// Returns true if SQLite provided sqlite3_normalized_sql, which is only
+            /// available when it was compiled with SQLITE_ENABLE_NORMALIZE.
+            pub(crate) unsafe fn has_normalized_sql() -> bool {
+                debug_assert!(!API.is_null(), "SQLite API not initialized");
+                (*API).normalized_sql.is_some()
+            }
         }
     };
 
@@ -5,12 +5,15 @@ use proc_macro::TokenStream;
 use proc_macro2::Span;
 use quote::{format_ident, quote, ToTokens};
 use regex::Regex;
+use row_derive::*;
 use std::mem::replace;
 use syn::{punctuated::Punctuated, *};
 use vtab_attr::*;
 
 mod ext_attr;
 mod fn_attr;
+mod row_attr;
+mod row_derive;
 mod vtab_attr;
 
 mod kw {
@@ -25,9 +28,13 @@ mod kw {
     syn::custom_keyword!(UpdateVTab);
     syn::custom_keyword!(deterministic);
     syn::custom_keyword!(export);
+    syn::custom_keyword!(hidden);
     syn::custom_keyword!(n_args);
     syn::custom_keyword!(persistent);
+    syn::custom_keyword!(primary_key);
+    syn::custom_keyword!(rename);
     syn::custom_keyword!(risk_level);
+    syn::custom_keyword!(without_rowid);
 }
 
 /// Declare the primary extension entry point for the crate.
@@ -420,6 +427,292 @@ pub fn sqlite3_ext_fn(attr: TokenStream, item: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
+/// Derive an implementation of ToRow for a struct.
+///
+/// Each named field of the struct becomes a column of the virtual table, in declaration
+/// order. The declared type of the column is inferred from the type of the field: integer
+/// types and `bool` are declared INTEGER, `f32` and `f64` are declared REAL, `String` is
+/// declared TEXT, and `Vec<u8>` and `Blob` are declared BLOB. `Option<T>` is declared the
+/// same as `T`. Columns of any other type are declared without a type.
+///
+/// Every field must be [Clone], and the cloned value must implement
+/// [ToContextResult](sqlite3_ext::function::ToContextResult). The field is cloned each time
+/// its column is read, so fields which are expensive to clone, such as large strings or
+/// blobs, are copied once for every row returned by a query which uses the column.
+///
+/// The generated schema can be customized using the same `#[sqlite(...)]` attributes as
+/// [macro@VTabSchema].
+///
+/// # Example
+///
+/// ```no_run
+/// use sqlite3_ext::{vtab::*, *};
+///
+/// #[derive(ToRow)]
+/// struct Person {
+///     name: String,
+///     age: i64,
+///     email: Option<String>,
+/// }
+/// ```
+#[proc_macro_derive(ToRow, attributes(sqlite))]
+pub fn derive_to_row(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    let schema = match RowSchema::parse(&input) {
+        Ok(x) => x,
+        Err(e) => return TokenStream::from(e.into_compile_error()),
+    };
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let declaration = schema.declaration();
+    let column_body = schema.column_body();
+    let expanded = quote! {
+        #[automatically_derived]
+        impl #impl_generics ::sqlite3_ext::vtab::ToRow for #ident #ty_generics #where_clause {
+            fn schema() -> ::std::string::String {
+                ::std::borrow::ToOwned::to_owned(#declaration)
+            }
+
+            fn column(
+                &self,
+                idx: usize,
+                context: &::sqlite3_ext::vtab::ColumnContext,
+            ) -> ::sqlite3_ext::Result<()> {
+                #column_body
+            }
+        }
+    };
+    TokenStream::from(expanded)
+}
+
+/// Derive [FromRow](sqlite3_ext::FromRow) for a struct.
+///
+/// Each field is converted from a single column using [FromSql](sqlite3_ext::FromSql). For
+/// a struct with named fields, the columns of a query result are matched to fields by
+/// name, and it is an error if any column is missing. Values passed to
+/// [FromRow::from_row](sqlite3_ext::FromRow::from_row), as well as the columns of a query
+/// result for a tuple struct, are matched to fields by position.
+///
+/// # Syntax
+///
+/// Named fields can be annotated with `#[sqlite(rename = "...")]` to read the field from a
+/// column with a different name. The other attributes supported by [macro@VTabSchema] are
+/// accepted and ignored, so the same struct can derive both.
+///
+/// # Example
+///
+/// ```no_run
+/// use sqlite3_ext::*;
+///
+/// #[derive(FromRow)]
+/// struct Person {
+///     name: String,
+///     #[sqlite(rename = "years")]
+///     age: i64,
+///     email: Option<String>,
+/// }
+///
+/// #[derive(FromRow)]
+/// struct Count(i64);
+///
+/// fn oldest(db: &Connection) -> Result<Person> {
+///     db.query_one("SELECT name, years, email FROM people ORDER BY years DESC", ())
+/// }
+/// ```
+#[proc_macro_derive(FromRow, attributes(sqlite))]
+pub fn derive_from_row(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    let row = match ValueStruct::parse(&input) {
+        Ok(x) => x,
+        Err(e) => return TokenStream::from(e.into_compile_error()),
+    };
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let values_body = row.values_body();
+    let from_query_result = row.query_result_body().map(|body| {
+        quote! {
+            fn from_query_result(
+                row: &mut ::sqlite3_ext::query::QueryResult<'_>,
+            ) -> ::sqlite3_ext::Result<Self> {
+                #body
+            }
+        }
+    });
+    let expanded = quote! {
+        #[automatically_derived]
+        impl #impl_generics ::sqlite3_ext::FromRow for #ident #ty_generics #where_clause {
+            fn from_row(
+                values: &mut [&mut ::sqlite3_ext::ValueRef],
+            ) -> ::sqlite3_ext::Result<Self> {
+                #values_body
+            }
+
+            #from_query_result
+        }
+    };
+    TokenStream::from(expanded)
+}
+
+/// Derive [Params](sqlite3_ext::query::Params) for a struct.
+///
+/// Each field is bound to the named parameter with the same name, using any of the `:`,
+/// `@`, or `$` prefixes. Binding fails if the statement has no matching parameter. See
+/// [Statement::find_parameter](sqlite3_ext::query::Statement::find_parameter).
+///
+/// The struct is consumed and each field is bound by value, so the type of every field must
+/// implement [ToParam](sqlite3_ext::query::ToParam). A type which only implements
+/// [ToSql](sqlite3_ext::query::ToSql) can be bound by using a reference as the field type,
+/// for example `&'a T`.
+///
+/// # Syntax
+///
+/// Fields can be annotated with `#[sqlite(rename = "...")]` to bind the field to a
+/// parameter with a different name. The other attributes supported by [macro@VTabSchema]
+/// are accepted and ignored, so the same struct can derive both.
+///
+/// # Example
+///
+/// ```no_run
+/// use sqlite3_ext::*;
+///
+/// #[derive(Params)]
+/// struct NewUser<'a> {
+///     name: &'a str,
+///     #[sqlite(rename = "years")]
+///     age: i64,
+/// }
+///
+/// fn add_user(db: &Connection, user: NewUser) -> Result<i64> {
+///     db.insert("INSERT INTO users (name, age) VALUES (:name, @years)", user)
+/// }
+/// ```
+#[proc_macro_derive(Params, attributes(sqlite))]
+pub fn derive_params(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    let body = match ValueStruct::parse(&input).and_then(|x| x.bind_params_body(&input)) {
+        Ok(x) => x,
+        Err(e) => return TokenStream::from(e.into_compile_error()),
+    };
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let expanded = quote! {
+        #[automatically_derived]
+        impl #impl_generics ::sqlite3_ext::query::Params for #ident #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn bind_params(
+                self,
+                stmt: &mut ::sqlite3_ext::query::Statement,
+            ) -> ::sqlite3_ext::Result<()> {
+                #body
+            }
+        }
+    };
+    TokenStream::from(expanded)
+}
+
+/// Derive the schema of a virtual table from a struct.
+///
+/// This macro implements [VTabSchema](sqlite3_ext::vtab::VTabSchema) and
+/// [ToRow](sqlite3_ext::vtab::ToRow) for the struct, and declares a constant containing the
+/// index of each column. The constants are named after the fields, so a field named
+/// `start` will have its index available as `COLUMN_START`. The column types are inferred
+/// as described in [macro@ToRow].
+///
+/// # Syntax
+///
+/// Fields can be annotated with `#[sqlite(...)]`, using comma-separated values from the
+/// following list:
+///
+/// - `hidden` declares the column HIDDEN.
+/// - `rename = "..."` overrides the name of the column, which defaults to the name of the
+///   field.
+/// - `type = "..."` overrides the declared type of the column.
+/// - `primary_key` adds the column to the PRIMARY KEY of the table.
+///
+/// The struct itself can be annotated with `#[sqlite(without_rowid)]` to declare the table
+/// WITHOUT ROWID. Such tables need at least one `primary_key` field, and SQLite only allows
+/// a WITHOUT ROWID virtual table to be writable if its PRIMARY KEY is a single column.
+///
+/// # Example
+///
+/// ```no_run
+/// use sqlite3_ext::{vtab::*, *};
+///
+/// #[derive(VTabSchema)]
+/// struct Series {
+///     value: i64,
+///     #[sqlite(hidden)]
+///     start: i64,
+///     #[sqlite(hidden)]
+///     stop: i64,
+/// }
+///
+/// assert_eq!(
+///     Series::DECLARATION,
+///     "CREATE TABLE x ( \"value\" INTEGER, \"start\" INTEGER HIDDEN, \"stop\" INTEGER HIDDEN )"
+/// );
+/// assert_eq!(Series::COLUMN_STOP, 2);
+/// ```
+#[proc_macro_derive(VTabSchema, attributes(sqlite))]
+pub fn derive_vtab_schema(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    let schema = match RowSchema::parse(&input) {
+        Ok(x) => x,
+        Err(e) => return TokenStream::from(e.into_compile_error()),
+    };
+    let ident = &input.ident;
+    let vis = &input.vis;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let declaration = schema.declaration();
+    let column_body = schema.column_body();
+    let names = schema.fields.iter().map(|f| &f.name);
+    let primary_key = match schema.primary_key() {
+        Some(idx) => quote!(::std::option::Option::Some(#idx)),
+        None => quote!(::std::option::Option::None),
+    };
+    let consts = schema.fields.iter().enumerate().map(|(i, f)| {
+        let doc = format!("Index of the `{}` column.", f.name);
+        let field = f.ident.to_string();
+        let field = field.strip_prefix("r#").unwrap_or(&field);
+        let name = format_ident!("COLUMN_{}", field.to_case(Case::UpperSnake));
+        quote! {
+            #[doc = #doc]
+            #vis const #name: usize = #i;
+        }
+    });
+    let expanded = quote! {
+        #[automatically_derived]
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #(#consts)*
+        }
+
+        #[automatically_derived]
+        impl #impl_generics ::sqlite3_ext::vtab::VTabSchema for #ident #ty_generics #where_clause {
+            const DECLARATION: &'static str = #declaration;
+            const COLUMNS: &'static [&'static str] = &[#(#names),*];
+            const PRIMARY_KEY: ::std::option::Option<usize> = #primary_key;
+        }
+
+        #[automatically_derived]
+        impl #impl_generics ::sqlite3_ext::vtab::ToRow for #ident #ty_generics #where_clause {
+            fn schema() -> ::std::string::String {
+                ::std::borrow::ToOwned::to_owned(
+                    <Self as ::sqlite3_ext::vtab::VTabSchema>::DECLARATION,
+                )
+            }
+
+            fn column(
+                &self,
+                idx: usize,
+                context: &::sqlite3_ext::vtab::ColumnContext,
+            ) -> ::sqlite3_ext::Result<()> {
+                #column_body
+            }
+        }
+    };
+    TokenStream::from(expanded)
+}
+
 #[doc(hidden)]
 #[proc_macro]
 pub fn sqlite3_ext_doctest_impl(item: TokenStream) -> TokenStream {
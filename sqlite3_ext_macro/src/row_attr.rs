@@ -0,0 +1,53 @@
+use super::kw;
+use syn::{
+    parse::{Parse, ParseStream},
+    *,
+};
+
+/// An attribute applied to a field with `#[sqlite(...)]`.
+pub enum RowFieldAttr {
+    Hidden,
+    PrimaryKey,
+    Rename(LitStr),
+    Type(LitStr),
+}
+
+/// An attribute applied to a struct with `#[sqlite(...)]`.
+pub enum RowAttr {
+    WithoutRowid,
+}
+
+impl Parse for RowFieldAttr {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let lookahead = input.lookahead1();
+        if lookahead.peek(kw::hidden) {
+            input.parse::<kw::hidden>()?;
+            Ok(RowFieldAttr::Hidden)
+        } else if lookahead.peek(kw::primary_key) {
+            input.parse::<kw::primary_key>()?;
+            Ok(RowFieldAttr::PrimaryKey)
+        } else if lookahead.peek(kw::rename) {
+            input.parse::<kw::rename>()?;
+            input.parse::<Token![=]>()?;
+            input.parse().map(RowFieldAttr::Rename)
+        } else if lookahead.peek(Token![type]) {
+            input.parse::<Token![type]>()?;
+            input.parse::<Token![=]>()?;
+            input.parse().map(RowFieldAttr::Type)
+        } else {
+            Err(lookahead.error())
+        }
+    }
+}
+
+impl Parse for RowAttr {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let lookahead = input.lookahead1();
+        if lookahead.peek(kw::without_rowid) {
+            input.parse::<kw::without_rowid>()?;
+            Ok(RowAttr::WithoutRowid)
+        } else {
+            Err(lookahead.error())
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use super::row_attr::*;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{punctuated::Punctuated, *};
+
+/// The columns of a virtual table, derived from a struct.
+pub struct RowSchema {
+    pub fields: Vec<RowField>,
+    pub without_rowid: bool,
+}
+
+/// A column derived from a named struct field.
+pub struct RowField {
+    pub ident: Ident,
+    pub name: String,
+    pub decltype: Option<String>,
+    pub hidden: bool,
+    pub primary_key: bool,
+}
+
+impl RowSchema {
+    pub fn parse(input: &DeriveInput) -> Result<Self> {
+        let fields = match &input.data {
+            Data::Struct(DataStruct {
+                fields: Fields::Named(fields),
+                ..
+            }) => fields,
+            _ => {
+                return Err(Error::new_spanned(
+                    input,
+                    "only structs with named fields are supported",
+                ))
+            }
+        };
+        let mut without_rowid = false;
+        for attr in sqlite_attrs::<RowAttr>(&input.attrs)? {
+            match attr {
+                RowAttr::WithoutRowid => without_rowid = true,
+            }
+        }
+        let fields = fields
+            .named
+            .iter()
+            .map(RowField::parse)
+            .collect::<Result<Vec<_>>>()?;
+        if without_rowid && !fields.iter().any(|f| f.primary_key) {
+            return Err(Error::new_spanned(
+                input,
+                "without_rowid requires a primary_key field",
+            ));
+        }
+        Ok(RowSchema {
+            fields,
+            without_rowid,
+        })
+    }
+
+    /// Return the index of the PRIMARY KEY column, for WITHOUT ROWID tables with a
+    /// single-column PRIMARY KEY.
+    pub fn primary_key(&self) -> Option<usize> {
+        let mut keys = (0..self.fields.len()).filter(|&i| self.fields[i].primary_key);
+        match (self.without_rowid, keys.next(), keys.next()) {
+            (true, Some(idx), None) => Some(idx),
+            _ => None,
+        }
+    }
+
+    /// Return the CREATE TABLE statement for this schema.
+    pub fn declaration(&self) -> String {
+        let mut defs: Vec<_> = self.fields.iter().map(|f| f.definition()).collect();
+        let pk: Vec<_> = self
+            .fields
+            .iter()
+            .filter(|f| f.primary_key)
+            .map(|f| f.quoted_name())
+            .collect();
+        if !pk.is_empty() {
+            defs.push(format!("PRIMARY KEY ({})", pk.join(", ")));
+        }
+        let mut ret = format!("CREATE TABLE x ( {} )", defs.join(", "));
+        if self.without_rowid {
+            ret.push_str(" WITHOUT ROWID");
+        }
+        ret
+    }
+
+    /// Return the body of ToRow::column for this schema.
+    pub fn column_body(&self) -> TokenStream {
+        let arms = self.fields.iter().enumerate().map(|(i, f)| {
+            let field = &f.ident;
+            quote!(#i => context.set_result(::std::clone::Clone::clone(&self.#field)))
+        });
+        quote! {
+            match idx {
+                #(#arms,)*
+                _ => ::std::result::Result::Ok(()),
+            }
+        }
+    }
+}
+
+impl RowField {
+    fn parse(field: &Field) -> Result<Self> {
+        let ident = field.ident.clone().unwrap();
+        let name = ident.to_string();
+        let name = name.strip_prefix("r#").unwrap_or(&name).to_owned();
+        let mut ret = RowField {
+            ident,
+            name,
+            decltype: infer_decltype(&field.ty).map(String::from),
+            hidden: false,
+            primary_key: false,
+        };
+        for attr in sqlite_attrs::<RowFieldAttr>(&field.attrs)? {
+            match attr {
+                RowFieldAttr::Hidden => ret.hidden = true,
+                RowFieldAttr::PrimaryKey => ret.primary_key = true,
+                RowFieldAttr::Rename(n) => ret.name = n.value(),
+                RowFieldAttr::Type(t) => ret.decltype = Some(t.value()),
+            }
+        }
+        Ok(ret)
+    }
+
+    fn quoted_name(&self) -> String {
+        format!("\"{}\"", self.name.replace('"', "\"\""))
+    }
+
+    /// Return the column definition, as it should appear in a CREATE TABLE statement.
+    fn definition(&self) -> String {
+        let mut ret = self.quoted_name();
+        if let Some(t) = &self.decltype {
+            ret.push(' ');
+            ret.push_str(t);
+        }
+        if self.hidden {
+            ret.push_str(" HIDDEN");
+        }
+        ret
+    }
+}
+
+/// A struct whose fields are each a single SQLite value, for deriving FromRow and Params.
+pub struct ValueStruct {
+    pub fields: Vec<ValueField>,
+    pub named: bool,
+}
+
+/// A field which corresponds to a single column or parameter.
+pub struct ValueField {
+    pub member: Member,
+    pub name: Option<String>,
+}
+
+impl ValueStruct {
+    pub fn parse(input: &DeriveInput) -> Result<Self> {
+        let (fields, named) = match &input.data {
+            Data::Struct(DataStruct { fields, .. }) => (fields, matches!(fields, Fields::Named(_))),
+            _ => return Err(Error::new_spanned(input, "only structs are supported")),
+        };
+        let fields = fields
+            .iter()
+            .enumerate()
+            .map(|(i, f)| ValueField::parse(i, f))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ValueStruct { fields, named })
+    }
+
+    /// Return the body of FromRow::from_row, which maps the values by position.
+    pub fn values_body(&self) -> TokenStream {
+        let len = self.fields.len();
+        let check = if len > 0 {
+            quote! {
+                if values.len() < #len {
+                    return ::std::result::Result::Err(::sqlite3_ext::SQLITE_RANGE);
+                }
+            }
+        } else {
+            quote!()
+        };
+        let inits = self.fields.iter().enumerate().map(|(i, f)| {
+            let member = &f.member;
+            quote!(#member: ::sqlite3_ext::FromSql::from_sql(&mut *values[#i])?)
+        });
+        quote! {
+            #check
+            ::std::result::Result::Ok(Self { #(#inits),* })
+        }
+    }
+
+    /// Return the body of FromRow::from_query_result, which maps the columns by name. Tuple
+    /// structs use the default implementation, which maps the columns by position.
+    pub fn query_result_body(&self) -> Option<TokenStream> {
+        if !self.named {
+            return None;
+        }
+        let inits = self.fields.iter().map(|f| {
+            let member = &f.member;
+            let name = f.name.as_ref().unwrap();
+            quote! {
+                #member: ::sqlite3_ext::FromSql::from_sql(
+                    ::std::convert::AsMut::as_mut(row.get_by_name(#name)?),
+                )?
+            }
+        });
+        Some(quote!(::std::result::Result::Ok(Self { #(#inits),* })))
+    }
+
+    /// Return the body of Params::bind_params, which binds each field to the parameter
+    /// with the same name.
+    pub fn bind_params_body(&self, input: &DeriveInput) -> Result<TokenStream> {
+        if !self.named {
+            return Err(Error::new_spanned(
+                input,
+                "only structs with named fields are supported",
+            ));
+        }
+        let binds = self.fields.iter().map(|f| {
+            let member = &f.member;
+            let name = f.name.as_ref().unwrap();
+            quote! {
+                let pos = stmt.find_parameter(#name)?.get();
+                ::sqlite3_ext::query::ToParam::bind_param(self.#member, stmt, pos)?;
+            }
+        });
+        Ok(quote! {
+            #(#binds)*
+            ::std::result::Result::Ok(())
+        })
+    }
+}
+
+impl ValueField {
+    fn parse(index: usize, field: &Field) -> Result<Self> {
+        let (member, mut name) = match &field.ident {
+            Some(ident) => {
+                let name = ident.to_string();
+                let name = name.strip_prefix("r#").unwrap_or(&name).to_owned();
+                (Member::Named(ident.clone()), Some(name))
+            }
+            None => (Member::Unnamed(index.into()), None),
+        };
+        for attr in sqlite_attrs::<RowFieldAttr>(&field.attrs)? {
+            match attr {
+                RowFieldAttr::Rename(n) if name.is_some() => name = Some(n.value()),
+                RowFieldAttr::Rename(n) => {
+                    return Err(Error::new_spanned(
+                        n,
+                        "rename is only supported on named fields",
+                    ))
+                }
+                // These only affect the virtual table schema, and are accepted so that
+                // the same struct can also derive VTabSchema.
+                RowFieldAttr::Hidden | RowFieldAttr::PrimaryKey | RowFieldAttr::Type(_) => (),
+            }
+        }
+        Ok(ValueField { member, name })
+    }
+}
+
+/// Parse all `#[sqlite(...)]` attributes in the list.
+fn sqlite_attrs<T: parse::Parse>(attrs: &[Attribute]) -> Result<Vec<T>> {
+    let mut ret = vec![];
+    for attr in attrs.iter().filter(|a| a.path.is_ident("sqlite")) {
+        ret.extend(attr.parse_args_with(Punctuated::<T, Token![,]>::parse_terminated)?);
+    }
+    Ok(ret)
+}
+
+/// Infer the declared SQLite type for a Rust type, based on its name.
+fn infer_decltype(ty: &Type) -> Option<&'static str> {
+    let segment = match ty {
+        Type::Path(TypePath { path, .. }) => path.segments.last()?,
+        Type::Reference(TypeReference { elem, .. }) => return infer_decltype(elem),
+        _ => return None,
+    };
+    let inner = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first() {
+            Some(GenericArgument::Type(t)) => Some(t),
+            _ => None,
+        },
+        _ => None,
+    };
+    match segment.ident.to_string().as_str() {
+        "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize"
+        | "bool" => Some("INTEGER"),
+        "f32" | "f64" => Some("REAL"),
+        "String" | "str" => Some("TEXT"),
+        "Blob" => Some("BLOB"),
+        "Vec" => match inner {
+            Some(Type::Path(TypePath { path, .. })) if path.is_ident("u8") => Some("BLOB"),
+            _ => None,
+        },
+        "Option" => inner.and_then(infer_decltype),
+        _ => None,
+    }
+}
@@ -150,7 +150,10 @@ impl Connection {
     /// [SQLITE_DBCONFIG_DEFENSIVE](https://www.sqlite.org/c3ref/c_dbconfig_defensive.html#sqlitedbconfigdefensive)
     /// for details.
     ///
-    /// Requires SQLite 3.26.0. On earlier versions, this method is a no-op.
+    /// Requires SQLite 3.26.0. On earlier versions, this method is a no-op. Use
+    /// [db_config_set](Connection::db_config_set) with
+    /// [DbConfig::Defensive](crate::DbConfig::Defensive) to detect unsupported versions
+    /// instead.
     pub fn db_config_defensive(&self, enable: bool) -> Result<()> {
         let _ = enable;
         sqlite3_match_version! {
@@ -228,9 +231,11 @@ impl Database {
             )
         });
         match rc {
-            Ok(()) => Ok(Database {
-                db: unsafe { *db.as_ptr() },
-            }),
+            Ok(()) => {
+                let db = unsafe { *db.as_ptr() };
+                crate::progress::manage_handler(db);
+                Ok(Database { db })
+            }
             Err(e) => {
                 if !db.as_ptr().is_null() {
                     // Panic if we can't close the database we failed to open
@@ -244,6 +249,11 @@ impl Database {
     /// Gracefully close the database. This automatically happens when the Database is
     /// dropped, but a failure in drop will result in a panic, while this method provides a
     /// path for graceful error handling.
+    ///
+    /// [Statements](crate::query::Statement) borrow the Database they were prepared on, so
+    /// they are always finalized before this method can be called. If the close still fails
+    /// (for example, because of statements prepared through the raw ffi), the Database is
+    /// returned along with the error.
     pub fn close(mut self) -> std::result::Result<(), (Error, Database)> {
         match self._close() {
             Ok(()) => Ok(()),
@@ -253,6 +263,7 @@ impl Database {
 
     fn _close(&mut self) -> Result<()> {
         Error::from_sqlite(unsafe { ffi::sqlite3_close(self.db) })?;
+        crate::progress::forget_handler(self.db);
         self.db = null_mut();
         Ok(())
     }
@@ -0,0 +1,267 @@
+use super::{ffi, sqlite3_require_version, types::*, Connection};
+use std::{ffi::CString, os::raw::c_int};
+
+/// A boolean configuration option for a database connection. See
+/// [Connection::db_config_get] and [Connection::db_config_set].
+///
+/// Each option is only available in recent versions of SQLite. The minimum version is listed
+/// on each variant, and is also available from [min_version](DbConfig::min_version).
+///
+/// For more information, see [the SQLite documentation](https://www.sqlite.org/c3ref/c_dbconfig_defensive.html).
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum DbConfig {
+    /// Enforce foreign key constraints. Requires SQLite 3.7.0.
+    EnableFkey,
+    /// Enable triggers. Requires SQLite 3.7.0.
+    EnableTrigger,
+    /// Enable the two-argument version of the fts3_tokenizer function. Requires SQLite
+    /// 3.12.0.
+    EnableFts3Tokenizer,
+    /// Enable the sqlite3_load_extension C API. Requires SQLite 3.13.0.
+    EnableLoadExtension,
+    /// Do not checkpoint the write-ahead log when the connection is closed. Requires SQLite
+    /// 3.16.2.
+    NoCkptOnClose,
+    /// Enable the query planner stability guarantee. Requires SQLite 3.20.0.
+    EnableQpsg,
+    /// Include information about triggers in the output of EXPLAIN QUERY PLAN. Requires
+    /// SQLite 3.22.0.
+    TriggerEqp,
+    /// Used in conjunction with VACUUM to reset a database back to an empty database with
+    /// no schema and no content. Requires SQLite 3.24.0.
+    ResetDatabase,
+    /// Disable language features which allow ordinary SQL to deliberately corrupt the
+    /// database file. Requires SQLite 3.26.0.
+    Defensive,
+    /// Allow the sqlite_schema table to be written to. Requires SQLite 3.28.0.
+    WritableSchema,
+    /// Enable the legacy behavior of ALTER TABLE RENAME. Requires SQLite 3.29.0.
+    LegacyAlterTable,
+    /// Allow double-quoted string literals in DML statements. Requires SQLite 3.29.0.
+    DqsDml,
+    /// Allow double-quoted string literals in DDL statements. Requires SQLite 3.29.0.
+    DqsDdl,
+    /// Allow CREATE VIEW statements and the use of views. Requires SQLite 3.30.0.
+    EnableView,
+    /// Create new databases using the legacy file format. Requires SQLite 3.31.0.
+    LegacyFileFormat,
+    /// Allow SQL functions and virtual tables which are not marked as innocuous to be used
+    /// from within the schema. Requires SQLite 3.31.0.
+    TrustedSchema,
+}
+
+impl DbConfig {
+    /// Returns the earliest version of SQLite which supports this option.
+    pub fn min_version(self) -> i32 {
+        match self {
+            DbConfig::EnableFkey | DbConfig::EnableTrigger => 3_007_000,
+            DbConfig::EnableFts3Tokenizer => 3_012_000,
+            DbConfig::EnableLoadExtension => 3_013_000,
+            DbConfig::NoCkptOnClose => 3_016_002,
+            DbConfig::EnableQpsg => 3_020_000,
+            DbConfig::TriggerEqp => 3_022_000,
+            DbConfig::ResetDatabase => 3_024_000,
+            DbConfig::Defensive => 3_026_000,
+            DbConfig::WritableSchema => 3_028_000,
+            DbConfig::LegacyAlterTable | DbConfig::DqsDml | DbConfig::DqsDdl => 3_029_000,
+            DbConfig::EnableView => 3_030_000,
+            DbConfig::LegacyFileFormat | DbConfig::TrustedSchema => 3_031_000,
+        }
+    }
+
+    // Each option is guarded by sqlite3_require_version, like the other version-specific
+    // APIs, rather than by comparing against min_version at runtime.
+    #[cfg_attr(not(modern_sqlite), allow(unused))]
+    fn to_sqlite(self) -> Result<c_int> {
+        match self {
+            DbConfig::EnableFkey => {
+                sqlite3_require_version!(3_007_000, Ok(ffi::SQLITE_DBCONFIG_ENABLE_FKEY))
+            }
+            DbConfig::EnableTrigger => {
+                sqlite3_require_version!(3_007_000, Ok(ffi::SQLITE_DBCONFIG_ENABLE_TRIGGER))
+            }
+            DbConfig::EnableFts3Tokenizer => {
+                sqlite3_require_version!(3_012_000, Ok(ffi::SQLITE_DBCONFIG_ENABLE_FTS3_TOKENIZER))
+            }
+            DbConfig::EnableLoadExtension => {
+                sqlite3_require_version!(3_013_000, Ok(ffi::SQLITE_DBCONFIG_ENABLE_LOAD_EXTENSION))
+            }
+            DbConfig::NoCkptOnClose => {
+                sqlite3_require_version!(3_016_002, Ok(ffi::SQLITE_DBCONFIG_NO_CKPT_ON_CLOSE))
+            }
+            DbConfig::EnableQpsg => {
+                sqlite3_require_version!(3_020_000, Ok(ffi::SQLITE_DBCONFIG_ENABLE_QPSG))
+            }
+            DbConfig::TriggerEqp => {
+                sqlite3_require_version!(3_022_000, Ok(ffi::SQLITE_DBCONFIG_TRIGGER_EQP))
+            }
+            DbConfig::ResetDatabase => {
+                sqlite3_require_version!(3_024_000, Ok(ffi::SQLITE_DBCONFIG_RESET_DATABASE))
+            }
+            DbConfig::Defensive => {
+                sqlite3_require_version!(3_026_000, Ok(ffi::SQLITE_DBCONFIG_DEFENSIVE))
+            }
+            DbConfig::WritableSchema => {
+                sqlite3_require_version!(3_028_000, Ok(ffi::SQLITE_DBCONFIG_WRITABLE_SCHEMA))
+            }
+            DbConfig::LegacyAlterTable => {
+                sqlite3_require_version!(3_029_000, Ok(ffi::SQLITE_DBCONFIG_LEGACY_ALTER_TABLE))
+            }
+            DbConfig::DqsDml => {
+                sqlite3_require_version!(3_029_000, Ok(ffi::SQLITE_DBCONFIG_DQS_DML))
+            }
+            DbConfig::DqsDdl => {
+                sqlite3_require_version!(3_029_000, Ok(ffi::SQLITE_DBCONFIG_DQS_DDL))
+            }
+            DbConfig::EnableView => {
+                sqlite3_require_version!(3_030_000, Ok(ffi::SQLITE_DBCONFIG_ENABLE_VIEW))
+            }
+            DbConfig::LegacyFileFormat => {
+                sqlite3_require_version!(3_031_000, Ok(ffi::SQLITE_DBCONFIG_LEGACY_FILE_FORMAT))
+            }
+            DbConfig::TrustedSchema => {
+                sqlite3_require_version!(3_031_000, Ok(ffi::SQLITE_DBCONFIG_TRUSTED_SCHEMA))
+            }
+        }
+    }
+}
+
+impl Connection {
+    /// Returns the current value of the configuration option.
+    ///
+    /// Returns [Error::VersionNotSatisfied] if the running version of SQLite does not
+    /// support the option.
+    pub fn db_config_get(&self, opt: DbConfig) -> Result<bool> {
+        self.db_config_bool(opt, -1)
+    }
+
+    /// Change the value of the configuration option, returning the previous value.
+    ///
+    /// Returns [Error::VersionNotSatisfied] if the running version of SQLite does not
+    /// support the option.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use sqlite3_ext::*;
+    ///
+    /// fn harden(db: &Connection) -> Result<()> {
+    ///     db.db_config_set(DbConfig::Defensive, true)?;
+    ///     db.db_config_set(DbConfig::TrustedSchema, false)?;
+    ///     db.db_config_set(DbConfig::DqsDml, false)?;
+    ///     db.db_config_set(DbConfig::DqsDdl, false)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn db_config_set(&self, opt: DbConfig, value: bool) -> Result<bool> {
+        let prev = self.db_config_bool(opt, -1)?;
+        self.db_config_bool(opt, value as _)?;
+        Ok(prev)
+    }
+
+    /// Set the option to value, or leave it unchanged if value is negative. Returns the
+    /// value of the option after the change.
+    fn db_config_bool(&self, opt: DbConfig, value: c_int) -> Result<bool> {
+        let _ = (opt, value);
+        sqlite3_require_version!(3_007_000, {
+            let opt = opt.to_sqlite()?;
+            let mut ret: c_int = 0;
+            let guard = self.lock();
+            let rc = unsafe {
+                ffi::sqlite3_db_config()(self.as_mut_ptr(), opt, value, &mut ret as *mut c_int)
+            };
+            Error::from_sqlite_desc(rc, guard)?;
+            Ok(ret != 0)
+        })
+    }
+
+    /// Configure the lookaside memory allocator for this connection. SQLite will allocate
+    /// slot_count slots of slot_size bytes each. Passing 0 for either argument disables
+    /// lookaside memory.
+    ///
+    /// This method fails with SQLITE_BUSY if any lookaside memory is currently in use.
+    ///
+    /// Requires SQLite 3.7.0.
+    pub fn set_lookaside(&self, slot_size: i32, slot_count: i32) -> Result<()> {
+        let _ = (slot_size, slot_count);
+        sqlite3_require_version!(3_007_000, {
+            let guard = self.lock();
+            let rc = unsafe {
+                ffi::sqlite3_db_config()(
+                    self.as_mut_ptr(),
+                    ffi::SQLITE_DBCONFIG_LOOKASIDE,
+                    std::ptr::null_mut::<std::ffi::c_void>(),
+                    slot_size as c_int,
+                    slot_count as c_int,
+                )
+            };
+            Error::from_sqlite_desc(rc, guard)
+        })
+    }
+
+    /// Change the schema name of the main database for this connection.
+    ///
+    /// Requires SQLite 3.15.0.
+    ///
+    /// Note: SQLite does not copy the name, so it is leaked. SQLite does not provide any
+    /// facilities for cleaning up this data.
+    pub fn set_main_db_name(&self, name: &str) -> Result<()> {
+        let _ = name;
+        sqlite3_require_version!(3_015_000, {
+            let name = CString::new(name)?.into_raw();
+            let guard = self.lock();
+            let rc = unsafe {
+                ffi::sqlite3_db_config()(self.as_mut_ptr(), ffi::SQLITE_DBCONFIG_MAINDBNAME, name)
+            };
+            Error::from_sqlite_desc(rc, guard)
+        })
+    }
+}
+
+#[cfg(all(modern_sqlite, test, feature = "static"))]
+mod test {
+    use crate::test_helpers::prelude::*;
+
+    #[test]
+    fn get_set() -> Result<()> {
+        let h = TestHelpers::new();
+        let was = h.db.db_config_get(DbConfig::EnableFkey)?;
+        assert_eq!(h.db.db_config_set(DbConfig::EnableFkey, !was)?, was);
+        assert_eq!(h.db.db_config_get(DbConfig::EnableFkey)?, !was);
+        let fk: bool =
+            h.db.query_row("PRAGMA foreign_keys", (), |r| Ok(r[0].get_i64() != 0))?;
+        assert_eq!(fk, !was);
+        Ok(())
+    }
+
+    #[test]
+    fn enable_view() -> Result<()> {
+        let h = TestHelpers::new();
+        h.db.execute("CREATE VIEW v AS SELECT 1", ())?;
+        h.db.query_row("SELECT * FROM v", (), |_| Ok(()))?;
+        assert!(h.db.db_config_set(DbConfig::EnableView, false)?);
+        assert!(h.db.query_row("SELECT * FROM v", (), |_| Ok(())).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn main_db_name() -> Result<()> {
+        let h = TestHelpers::new();
+        h.db.set_main_db_name("store")?;
+        h.db.execute("CREATE TABLE store.tbl(a)", ())?;
+        let name: String = h.db.query_row(
+            "SELECT name FROM pragma_database_list WHERE seq = 0",
+            (),
+            |r| Ok(r[0].get_str()?.to_owned()),
+        )?;
+        assert_eq!(name, "store");
+        Ok(())
+    }
+
+    #[test]
+    fn lookaside() -> Result<()> {
+        let h = TestHelpers::new();
+        h.db.set_lookaside(128, 16)?;
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use super::{ffi, sqlite3_match_version, sqlite3_require_version, types::*};
+use super::{ffi, sqlite3_match_version, sqlite3_require_version, types::*, Connection};
 use std::{cmp::Ordering, ffi::CStr, str};
 
 /// The version of SQLite.
@@ -108,6 +108,213 @@ pub fn sqlite3_randomness(n: usize) -> Vec<u8> {
     ret
 }
 
+/// A global runtime status counter. See [sqlite3_status].
+///
+/// For more information, see [the SQLite documentation](https://www.sqlite.org/c3ref/c_status_malloc_count.html).
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Status {
+    /// The current amount of memory checked out using the SQLite memory allocator, in bytes.
+    MemoryUsed,
+    /// The number of pages used out of the pagecache memory allocator.
+    PagecacheUsed,
+    /// The number of bytes of page cache allocation which could not be satisfied by the
+    /// pagecache memory allocator and were forced to overflow to the general allocator.
+    PagecacheOverflow,
+    /// The largest memory allocation request handed to the SQLite memory allocator, in
+    /// bytes. Only the highwater value is meaningful.
+    MallocSize,
+    /// The deepest parser stack. Only the highwater value is meaningful.
+    ParserStack,
+    /// The largest memory allocation request handed to the pagecache memory allocator, in
+    /// bytes. Only the highwater value is meaningful.
+    PagecacheSize,
+    /// The number of separate memory allocations currently checked out.
+    MallocCount,
+}
+
+impl Status {
+    fn to_sqlite(self) -> i32 {
+        match self {
+            Status::MemoryUsed => ffi::SQLITE_STATUS_MEMORY_USED,
+            Status::PagecacheUsed => ffi::SQLITE_STATUS_PAGECACHE_USED,
+            Status::PagecacheOverflow => ffi::SQLITE_STATUS_PAGECACHE_OVERFLOW,
+            Status::MallocSize => ffi::SQLITE_STATUS_MALLOC_SIZE,
+            Status::ParserStack => ffi::SQLITE_STATUS_PARSER_STACK,
+            Status::PagecacheSize => ffi::SQLITE_STATUS_PAGECACHE_SIZE,
+            Status::MallocCount => ffi::SQLITE_STATUS_MALLOC_COUNT,
+        }
+    }
+}
+
+/// Retrieve the current and highwater values of a global runtime status counter, in that
+/// order. If reset is true, the highwater mark is reset to the current value after it is
+/// retrieved.
+///
+/// On SQLite versions prior to 3.10.0, the values are limited to 32 bits.
+pub fn sqlite3_status(op: Status, reset: bool) -> Result<(i64, i64)> {
+    sqlite3_match_version! {
+        3_010_000 => {
+            let mut current: i64 = 0;
+            let mut highwater: i64 = 0;
+            Error::from_sqlite(unsafe {
+                ffi::sqlite3_status64(op.to_sqlite(), &mut current, &mut highwater, reset as _)
+            })?;
+            Ok((current, highwater))
+        }
+        _ => {
+            let mut current: i32 = 0;
+            let mut highwater: i32 = 0;
+            Error::from_sqlite(unsafe {
+                ffi::sqlite3_status(op.to_sqlite(), &mut current, &mut highwater, reset as _)
+            })?;
+            Ok((current as _, highwater as _))
+        }
+    }
+}
+
+/// Returns the number of bytes of memory currently outstanding (malloced but not freed).
+pub fn sqlite3_memory_used() -> i64 {
+    unsafe { ffi::sqlite3_memory_used() }
+}
+
+/// Returns the maximum value of [sqlite3_memory_used] since the high-water mark was last
+/// reset. If reset is true, the high-water mark is reset to the current value after it is
+/// retrieved.
+pub fn sqlite3_memory_highwater(reset: bool) -> i64 {
+    unsafe { ffi::sqlite3_memory_highwater(reset as _) }
+}
+
+/// Set the soft limit on the amount of heap memory that may be allocated by SQLite,
+/// returning the previous limit. If the limit is negative, the limit is unchanged. A limit
+/// of 0 disables the soft heap limit.
+///
+/// Requires SQLite 3.7.3.
+pub fn sqlite3_soft_heap_limit64(limit: i64) -> Result<i64> {
+    let _ = limit;
+    sqlite3_require_version!(
+        3_007_003,
+        Ok(unsafe { ffi::sqlite3_soft_heap_limit64(limit) })
+    )
+}
+
+/// Set the hard limit on the amount of heap memory that may be allocated by SQLite,
+/// returning the previous limit. If the limit is negative, the limit is unchanged. A limit
+/// of 0 disables the hard heap limit.
+///
+/// Requires SQLite 3.31.0.
+pub fn sqlite3_hard_heap_limit64(limit: i64) -> Result<i64> {
+    let _ = limit;
+    sqlite3_require_version!(
+        3_031_000,
+        Ok(unsafe { ffi::sqlite3_hard_heap_limit64(limit) })
+    )
+}
+
+/// Attempt to free n bytes of heap memory by deallocating non-essential memory allocations
+/// held by the database library, returning the number of bytes actually freed.
+pub fn sqlite3_release_memory(n: i32) -> i32 {
+    unsafe { ffi::sqlite3_release_memory(n) }
+}
+
+/// A runtime status counter for a database connection. See [Connection::status].
+///
+/// Each counter was introduced in a different version of SQLite. Using a counter which is
+/// not supported by the current version of SQLite results in an error.
+///
+/// For more information, see [the SQLite documentation](https://www.sqlite.org/c3ref/c_dbstatus_options.html).
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum DbStatus {
+    /// The number of lookaside memory slots currently checked out.
+    LookasideUsed,
+    /// The approximate number of bytes of heap memory used by all pager caches.
+    CacheUsed,
+    /// The approximate number of bytes of heap memory used to store the schema for all
+    /// databases.
+    SchemaUsed,
+    /// The approximate number of bytes of heap and lookaside memory used by all prepared
+    /// statements.
+    StmtUsed,
+    /// The number of malloc attempts that were satisfied using lookaside memory. Only the
+    /// highwater value is meaningful.
+    LookasideHit,
+    /// The number of malloc attempts that might have been satisfied using lookaside memory
+    /// but failed due to the amount of memory requested. Only the highwater value is
+    /// meaningful.
+    LookasideMissSize,
+    /// The number of malloc attempts that might have been satisfied using lookaside memory
+    /// but failed due to all lookaside memory already being in use. Only the highwater
+    /// value is meaningful.
+    LookasideMissFull,
+    /// The number of pager cache hits. The highwater value is always 0.
+    CacheHit,
+    /// The number of pager cache misses. The highwater value is always 0.
+    CacheMiss,
+    /// The number of dirty cache entries that have been written to disk. The highwater
+    /// value is always 0.
+    CacheWrite,
+    /// The current value is 1 if all foreign key constraints have been resolved, and 0
+    /// otherwise. The highwater value is always 0.
+    DeferredFks,
+    /// Like [CacheUsed](DbStatus::CacheUsed), but shared pager caches are divided evenly
+    /// between the connections which share them.
+    CacheUsedShared,
+    /// The number of dirty cache entries that have been written to disk in the middle of a
+    /// transaction due to the page cache overflowing. The highwater value is always 0.
+    CacheSpill,
+}
+
+impl DbStatus {
+    fn to_sqlite(self) -> i32 {
+        match self {
+            DbStatus::LookasideUsed => ffi::SQLITE_DBSTATUS_LOOKASIDE_USED,
+            DbStatus::CacheUsed => ffi::SQLITE_DBSTATUS_CACHE_USED,
+            DbStatus::SchemaUsed => ffi::SQLITE_DBSTATUS_SCHEMA_USED,
+            DbStatus::StmtUsed => ffi::SQLITE_DBSTATUS_STMT_USED,
+            DbStatus::LookasideHit => ffi::SQLITE_DBSTATUS_LOOKASIDE_HIT,
+            DbStatus::LookasideMissSize => ffi::SQLITE_DBSTATUS_LOOKASIDE_MISS_SIZE,
+            DbStatus::LookasideMissFull => ffi::SQLITE_DBSTATUS_LOOKASIDE_MISS_FULL,
+            DbStatus::CacheHit => ffi::SQLITE_DBSTATUS_CACHE_HIT,
+            DbStatus::CacheMiss => ffi::SQLITE_DBSTATUS_CACHE_MISS,
+            DbStatus::CacheWrite => ffi::SQLITE_DBSTATUS_CACHE_WRITE,
+            DbStatus::DeferredFks => ffi::SQLITE_DBSTATUS_DEFERRED_FKS,
+            DbStatus::CacheUsedShared => ffi::SQLITE_DBSTATUS_CACHE_USED_SHARED,
+            DbStatus::CacheSpill => ffi::SQLITE_DBSTATUS_CACHE_SPILL,
+        }
+    }
+}
+
+impl Connection {
+    /// Retrieve the current and highwater values of a runtime status counter for this
+    /// connection, in that order. If reset is true, the highwater mark is reset to the
+    /// current value after it is retrieved.
+    pub fn status(&self, op: DbStatus, reset: bool) -> Result<(i32, i32)> {
+        let mut current: i32 = 0;
+        let mut highwater: i32 = 0;
+        Error::from_sqlite(unsafe {
+            ffi::sqlite3_db_status(
+                self.as_mut_ptr(),
+                op.to_sqlite(),
+                &mut current,
+                &mut highwater,
+                reset as _,
+            )
+        })?;
+        Ok((current, highwater))
+    }
+
+    /// Attempt to free as much heap memory as possible from this connection, without
+    /// affecting any open transactions.
+    ///
+    /// Requires SQLite 3.7.10.
+    pub fn release_memory(&self) -> Result<()> {
+        sqlite3_require_version!(3_007_010, {
+            let guard = self.lock();
+            let rc = unsafe { ffi::sqlite3_db_release_memory(self.as_mut_ptr()) };
+            Error::from_sqlite_desc(rc, guard)
+        })
+    }
+}
+
 #[cfg(all(test, feature = "static"))]
 mod test {
     use super::*;
@@ -143,6 +350,43 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn status() -> Result<()> {
+        let h = crate::test_helpers::TestHelpers::new();
+        h.db.execute("CREATE TABLE tbl(col)", ())?;
+        let (current, highwater) = sqlite3_status(Status::MemoryUsed, false)?;
+        assert!(current > 0);
+        assert!(highwater >= current);
+        assert!(sqlite3_memory_used() > 0);
+        assert!(sqlite3_memory_highwater(false) > 0);
+        let (schema, _) = h.db.status(DbStatus::SchemaUsed, false)?;
+        assert!(schema > 0);
+        sqlite3_match_version! {
+            3_007_010 => h.db.release_memory()?,
+            _ => (),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn heap_limit() -> Result<()> {
+        sqlite3_match_version! {
+            3_007_003 => {
+                let prev = sqlite3_soft_heap_limit64(-1)?;
+                assert_eq!(sqlite3_soft_heap_limit64(prev)?, prev);
+            }
+            _ => (),
+        }
+        sqlite3_match_version! {
+            3_031_000 => {
+                let prev = sqlite3_hard_heap_limit64(-1)?;
+                assert_eq!(sqlite3_hard_heap_limit64(prev)?, prev);
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
     #[test]
     fn randomness() {
         let ret = sqlite3_randomness(32);
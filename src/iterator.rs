@@ -48,8 +48,49 @@ pub trait FallibleIteratorMut {
     {
         Map { it: self, f }
     }
+
+    /// Convert this iterator into a [FallibleIterator] by applying a function to each
+    /// element, and skipping elements for which the function returns `None`.
+    #[inline]
+    fn filter_map<F, B>(&mut self, f: F) -> FilterMap<'_, Self, F>
+    where
+        Self: Sized,
+        F: FnMut(&mut Self::Item) -> Result<Option<B>, Self::Error>,
+    {
+        FilterMap { it: self, f }
+    }
+
+    /// Call a function on each remaining element. Iteration stops at the first error,
+    /// either from the iterator or from the function.
+    #[inline]
+    fn for_each<F>(&mut self, mut f: F) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+        F: FnMut(&mut Self::Item) -> Result<(), Self::Error>,
+    {
+        while let Some(x) = self.next()? {
+            f(x)?;
+        }
+        Ok(())
+    }
+
+    /// Apply a function to each remaining element, producing a single final value. See
+    /// [Iterator::try_fold].
+    #[inline]
+    fn try_fold<B, F>(&mut self, init: B, mut f: F) -> Result<B, Self::Error>
+    where
+        Self: Sized,
+        F: FnMut(B, &mut Self::Item) -> Result<B, Self::Error>,
+    {
+        let mut acc = init;
+        while let Some(x) = self.next()? {
+            acc = f(acc, x)?;
+        }
+        Ok(acc)
+    }
 }
 
+/// A [FallibleIterator] created by [FallibleIteratorMut::map].
 pub struct Map<'a, I, F> {
     it: &'a mut I,
     f: F,
@@ -77,3 +118,33 @@ where
         self.it.size_hint()
     }
 }
+
+/// A [FallibleIterator] created by [FallibleIteratorMut::filter_map].
+pub struct FilterMap<'a, I, F> {
+    it: &'a mut I,
+    f: F,
+}
+
+impl<'a, I, F, B> FallibleIterator for FilterMap<'a, I, F>
+where
+    I: FallibleIteratorMut,
+    F: FnMut(&mut I::Item) -> Result<Option<B>, I::Error>,
+{
+    type Item = B;
+    type Error = I::Error;
+
+    #[inline]
+    fn next(&mut self) -> Result<Option<B>, I::Error> {
+        while let Some(v) = self.it.next()? {
+            if let Some(x) = (self.f)(v)? {
+                return Ok(Some(x));
+            }
+        }
+        Ok(None)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.it.size_hint().1)
+    }
+}
@@ -1,26 +1,36 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 pub use connection::*;
+pub use db_config::*;
 pub use extension::Extension;
 pub use globals::*;
 pub use iterator::*;
+pub use limit::*;
+pub use row::*;
 pub use sqlite3_ext_macro::*;
 pub use transaction::*;
 pub use types::*;
 pub use value::*;
+pub use wal::*;
 
 mod connection;
+mod db_config;
 mod extension;
 pub mod ffi;
 pub mod function;
 mod globals;
 mod iterator;
+mod limit;
 mod mutex;
+mod progress;
 pub mod query;
+pub mod rarray;
+mod row;
 mod test_helpers;
 mod transaction;
 mod types;
 mod value;
 pub mod vtab;
+mod wal;
 mod with_rusqlite;
 
 /// Indicate the risk level for a function or virtual table.
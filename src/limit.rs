@@ -0,0 +1,109 @@
+use super::{ffi, Connection};
+use std::os::raw::c_int;
+
+/// A run-time limit on the size of constructs in a database connection. See
+/// [Connection::limit] and [Connection::set_limit].
+///
+/// For more information, see [the SQLite documentation](https://www.sqlite.org/c3ref/c_limit_attached.html).
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Limit {
+    /// The maximum size of any string or BLOB or table row, in bytes.
+    Length,
+    /// The maximum length of an SQL statement, in bytes.
+    SqlLength,
+    /// The maximum number of columns in a table definition or in the result set of a SELECT,
+    /// or the maximum number of columns in an index or in an ORDER BY or GROUP BY clause.
+    Column,
+    /// The maximum depth of the parse tree on any expression.
+    ExprDepth,
+    /// The maximum number of terms in a compound SELECT statement.
+    CompoundSelect,
+    /// The maximum number of instructions in a virtual machine program used to implement an
+    /// SQL statement. If sqlite3_prepare_v2() or the equivalent tries to allocate space for
+    /// more than this many opcodes in a single prepared statement, an SQLITE_NOMEM error is
+    /// returned.
+    VdbeOp,
+    /// The maximum number of arguments on a function.
+    FunctionArg,
+    /// The maximum number of attached databases.
+    Attached,
+    /// The maximum length of the pattern argument to the LIKE or GLOB operators.
+    LikePatternLength,
+    /// The maximum index number of any parameter in an SQL statement.
+    VariableNumber,
+    /// The maximum depth of recursion for triggers.
+    TriggerDepth,
+    /// The maximum number of auxiliary worker threads that a single prepared statement may
+    /// start. Requires SQLite 3.8.7.
+    WorkerThreads,
+}
+
+impl Limit {
+    fn to_sqlite(self) -> c_int {
+        match self {
+            Limit::Length => ffi::SQLITE_LIMIT_LENGTH,
+            Limit::SqlLength => ffi::SQLITE_LIMIT_SQL_LENGTH,
+            Limit::Column => ffi::SQLITE_LIMIT_COLUMN,
+            Limit::ExprDepth => ffi::SQLITE_LIMIT_EXPR_DEPTH,
+            Limit::CompoundSelect => ffi::SQLITE_LIMIT_COMPOUND_SELECT,
+            Limit::VdbeOp => ffi::SQLITE_LIMIT_VDBE_OP,
+            Limit::FunctionArg => ffi::SQLITE_LIMIT_FUNCTION_ARG,
+            Limit::Attached => ffi::SQLITE_LIMIT_ATTACHED,
+            Limit::LikePatternLength => ffi::SQLITE_LIMIT_LIKE_PATTERN_LENGTH,
+            Limit::VariableNumber => ffi::SQLITE_LIMIT_VARIABLE_NUMBER,
+            Limit::TriggerDepth => ffi::SQLITE_LIMIT_TRIGGER_DEPTH,
+            Limit::WorkerThreads => ffi::SQLITE_LIMIT_WORKER_THREADS,
+        }
+    }
+}
+
+impl Connection {
+    /// Returns the current value of the limit. If the limit is not supported by the current
+    /// version of SQLite, returns -1.
+    pub fn limit(&self, limit: Limit) -> i32 {
+        unsafe { ffi::sqlite3_limit(self.as_mut_ptr(), limit.to_sqlite(), -1) }
+    }
+
+    /// Change the value of the limit, returning the previous value. If the new value is
+    /// negative, the limit is unchanged.
+    ///
+    /// Attempts to increase a limit above the hard upper bound compiled into SQLite are
+    /// silently truncated to the hard upper bound. If the limit is not supported by the
+    /// current version of SQLite, returns -1.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use sqlite3_ext::*;
+    ///
+    /// fn sandbox(db: &Connection) {
+    ///     db.set_limit(Limit::Length, 1_000_000);
+    ///     db.set_limit(Limit::SqlLength, 100_000);
+    ///     db.set_limit(Limit::Attached, 0);
+    /// }
+    /// ```
+    pub fn set_limit(&self, limit: Limit, val: i32) -> i32 {
+        unsafe { ffi::sqlite3_limit(self.as_mut_ptr(), limit.to_sqlite(), val) }
+    }
+}
+
+#[cfg(all(test, feature = "static"))]
+mod test {
+    use crate::test_helpers::prelude::*;
+
+    #[test]
+    fn limit() -> Result<()> {
+        let h = TestHelpers::new();
+        let orig = h.db.limit(Limit::Length);
+        assert!(orig > 0);
+        assert_eq!(h.db.set_limit(Limit::Length, 10), orig);
+        assert_eq!(h.db.limit(Limit::Length), 10);
+        assert!(h
+            .db
+            .query_row("SELECT 'a very long string'", (), |_| Ok(()))
+            .is_err());
+        assert_eq!(h.db.set_limit(Limit::Length, -1), 10);
+        assert_eq!(h.db.limit(Limit::Length), 10);
+        Ok(())
+    }
+}
@@ -0,0 +1,306 @@
+use super::{ffi, types::*, Connection};
+use std::{
+    ffi::c_void,
+    os::raw::c_int,
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr::null_mut,
+    sync::{Mutex, MutexGuard},
+    time::Instant,
+};
+
+/// The number of virtual machine instructions between checks of a statement deadline.
+const DEADLINE_OPS: c_int = 1000;
+
+type Callback = unsafe extern "C" fn(*mut c_void) -> c_int;
+
+#[derive(Clone, Copy)]
+struct ProgressHandler {
+    n_ops: c_int,
+    callback: Callback,
+    user_data: usize,
+}
+
+// SQLite does not provide a way to retrieve the current progress handler, so the handler
+// of each connection whose progress handler is managed by this crate is recorded here, keyed
+// by the connection pointer. This allows the previous handler to be restored after a
+// statement with a deadline has been stepped. Connections opened by Database are managed
+// from the start, and are forgotten when the Database is closed. Other connections become
+// managed once a handler is set or removed using this crate, and are forgotten when SQLite
+// closes them, see track_connection.
+static HANDLERS: Mutex<Vec<(usize, Option<ProgressHandler>)>> = Mutex::new(Vec::new());
+
+/// The name of the collation used to detect when a managed connection is closed.
+const TRACKER_NAME: &[u8] = b"sqlite3_ext_progress_handler\0";
+
+fn handlers() -> MutexGuard<'static, Vec<(usize, Option<ProgressHandler>)>> {
+    HANDLERS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Ensure that the connection is removed from HANDLERS when it is closed, regardless of
+/// who closes it, so that a later connection which reuses the address is not mistaken for
+/// this one. SQLite destroys the collations of a connection when closing it, so a
+/// placeholder collation is registered whose destructor removes the entry. Returns false if
+/// the collation could not be registered, in which case the connection cannot be managed.
+///
+/// Must be called before the connection is added to HANDLERS, because replacing an
+/// existing collation destroys it.
+unsafe fn track_connection(db: *mut ffi::sqlite3) -> bool {
+    ffi::sqlite3_create_collation_v2(
+        db,
+        TRACKER_NAME.as_ptr() as _,
+        ffi::SQLITE_UTF8,
+        db as _,
+        Some(tracker_compare),
+        Some(tracker_destroy),
+    ) == ffi::SQLITE_OK
+}
+
+unsafe extern "C" fn tracker_compare(
+    _: *mut c_void,
+    _: c_int,
+    _: *const c_void,
+    _: c_int,
+    _: *const c_void,
+) -> c_int {
+    0
+}
+
+unsafe extern "C" fn tracker_destroy(db: *mut c_void) {
+    forget_handler(db as _);
+}
+
+/// Install the handler on the connection, returning the handler which was previously
+/// installed, or None if the connection was not managed by this crate.
+fn swap_handler(
+    db: &Connection,
+    handler: Option<ProgressHandler>,
+) -> Option<Option<ProgressHandler>> {
+    let db = unsafe { db.as_mut_ptr() };
+    let prev = handlers()
+        .iter_mut()
+        .find(|(x, _)| *x == db as usize)
+        .map(|(_, h)| std::mem::replace(h, handler));
+    if prev.is_none() && unsafe { track_connection(db) } {
+        handlers().push((db as usize, handler));
+    }
+    match handler {
+        Some(h) => unsafe {
+            ffi::sqlite3_progress_handler(db, h.n_ops, Some(h.callback), h.user_data as _)
+        },
+        None => unsafe { ffi::sqlite3_progress_handler(db, 0, None, null_mut()) },
+    }
+    prev
+}
+
+/// Record that a newly opened connection has no progress handler. The caller is responsible
+/// for calling forget_handler once the connection is closed.
+pub(crate) fn manage_handler(db: *mut ffi::sqlite3) {
+    handlers().push((db as usize, None));
+}
+
+/// Forget about a connection which has been closed.
+pub(crate) fn forget_handler(db: *mut ffi::sqlite3) {
+    handlers().retain(|(x, _)| *x != db as usize);
+}
+
+impl Connection {
+    /// Register a callback which is invoked periodically while statements are running on
+    /// this connection. The callback is invoked approximately every n_ops virtual machine
+    /// instructions. If it returns true, the running statement is interrupted and fails
+    /// with SQLITE_INTERRUPT. A value of 0 or less for n_ops disables the callback.
+    ///
+    /// While a statement with a deadline is being stepped, this handler is temporarily
+    /// replaced. See [Statement::with_deadline](crate::query::Statement::with_deadline).
+    ///
+    /// If the callback panics, the statement is interrupted.
+    ///
+    /// Note: the provided function and any captured variables will be leaked. SQLite does
+    /// not provide any facilities for cleaning up this data.
+    pub fn set_progress_handler<F: Fn() -> bool + 'static>(&self, n_ops: i32, func: F) {
+        let func = Box::new(func);
+        let _guard = self.lock();
+        swap_handler(
+            self,
+            Some(ProgressHandler {
+                n_ops,
+                callback: progress_handler::<F>,
+                user_data: Box::into_raw(func) as usize,
+            }),
+        );
+    }
+
+    /// Remove the callback registered with
+    /// [set_progress_handler](Connection::set_progress_handler), or any progress handler
+    /// installed by other means.
+    ///
+    /// This also allows [Statement::with_deadline](crate::query::Statement::with_deadline)
+    /// to be used on connections which were not opened by [Database](crate::Database). The
+    /// crate stops tracking the connection when it is closed, regardless of who closes it.
+    pub fn remove_progress_handler(&self) {
+        let _guard = self.lock();
+        swap_handler(self, None);
+    }
+}
+
+unsafe extern "C" fn progress_handler<F: Fn() -> bool>(user_data: *mut c_void) -> c_int {
+    let func = &*(user_data as *const F);
+    // Unwinding across the FFI boundary is undefined behavior, so a panic interrupts the
+    // statement instead.
+    catch_unwind(AssertUnwindSafe(func)).unwrap_or(true) as _
+}
+
+struct Deadline {
+    at: Instant,
+    expired: bool,
+}
+
+unsafe extern "C" fn deadline_handler(user_data: *mut c_void) -> c_int {
+    let deadline = &mut *(user_data as *mut Deadline);
+    deadline.expired = Instant::now() >= deadline.at;
+    deadline.expired as _
+}
+
+/// Step the statement, interrupting it if it is still running at the deadline. The progress
+/// handler of the connection is restored afterwards. Fails with SQLITE_MISUSE if the
+/// progress handler of the connection is not managed by this crate, since it could not be
+/// restored.
+///
+/// The connection mutex must be held by the caller.
+pub(crate) unsafe fn step_with_deadline(
+    db: &Connection,
+    stmt: *mut ffi::sqlite3_stmt,
+    at: Instant,
+) -> Result<c_int> {
+    let key = db.as_mut_ptr() as usize;
+    if !handlers().iter().any(|(x, _)| *x == key) {
+        return Err(Error::Sqlite(
+            ffi::SQLITE_MISUSE,
+            Some(
+                "the progress handler of this connection is not managed by sqlite3_ext".to_owned(),
+            ),
+        ));
+    }
+    if Instant::now() >= at {
+        return Err(Error::DeadlineExceeded);
+    }
+    let mut deadline = Deadline { at, expired: false };
+    let prev = swap_handler(
+        db,
+        Some(ProgressHandler {
+            n_ops: DEADLINE_OPS,
+            callback: deadline_handler,
+            user_data: &mut deadline as *mut Deadline as usize,
+        }),
+    );
+    let rc = ffi::sqlite3_step(stmt);
+    swap_handler(db, prev.flatten());
+    if rc == ffi::SQLITE_INTERRUPT && deadline.expired {
+        Err(Error::DeadlineExceeded)
+    } else {
+        Ok(rc)
+    }
+}
+
+#[cfg(all(test, feature = "static"))]
+mod test {
+    use crate::test_helpers::prelude::*;
+    use std::{
+        cell::Cell,
+        ptr::{null, null_mut},
+        rc::Rc,
+        time::{Duration, Instant},
+    };
+
+    const SLOW_QUERY: &str = "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) \
+                              SELECT COUNT(*) FROM c";
+
+    /// Install a progress handler which counts its calls, and interrupts the statement once
+    /// it has been called limit times.
+    fn counting_handler(db: &Connection, n_ops: i32, limit: i32) -> Rc<Cell<i32>> {
+        let calls = Rc::new(Cell::new(0));
+        let calls_inner = calls.clone();
+        db.set_progress_handler(n_ops, move || {
+            calls_inner.set(calls_inner.get() + 1);
+            calls_inner.get() >= limit
+        });
+        calls
+    }
+
+    #[test]
+    fn progress_handler() -> Result<()> {
+        let h = TestHelpers::new();
+        let calls = counting_handler(&h.db, 100, 10);
+        match h.db.query_row(SLOW_QUERY, (), |_| Ok(())) {
+            Err(Error::Sqlite(ffi::SQLITE_INTERRUPT, _)) => (),
+            x => panic!("expected SQLITE_INTERRUPT, got {:?}", x),
+        }
+        assert_eq!(calls.get(), 10);
+        h.db.remove_progress_handler();
+        h.db.query_row("SELECT 1", (), |_| Ok(()))?;
+        assert_eq!(calls.get(), 10);
+        Ok(())
+    }
+
+    #[test]
+    fn deadline() -> Result<()> {
+        let h = TestHelpers::new();
+        let calls = counting_handler(&h.db, 1, i32::MAX);
+
+        let mut stmt = h.db.prepare(SLOW_QUERY)?;
+        stmt.with_deadline(Instant::now() + Duration::from_millis(50));
+        match stmt.query_row((), |_| Ok(())) {
+            Err(Error::DeadlineExceeded) => (),
+            x => panic!("expected Error::DeadlineExceeded, got {:?}", x),
+        }
+        assert_eq!(calls.get(), 0);
+
+        // A deadline which has already passed fails without running the statement.
+        match stmt.query_row((), |_| Ok(())) {
+            Err(Error::DeadlineExceeded) => (),
+            x => panic!("expected Error::DeadlineExceeded, got {:?}", x),
+        }
+
+        // The connection-level handler is restored after the statement is stepped.
+        h.db.query_row("SELECT 1", (), |_| Ok(()))?;
+        assert!(calls.get() > 0);
+
+        let ret =
+            h.db.prepare("SELECT 1")?
+                .with_deadline(Instant::now() + Duration::from_secs(60))
+                .query_row((), |r| Ok(r[0].get_i64()))?;
+        assert_eq!(ret, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn unmanaged_handler() -> Result<()> {
+        // A connection opened outside of this crate may have a progress handler which could
+        // not be restored.
+        let mut ptr = null_mut();
+        Error::from_sqlite(unsafe {
+            ffi::sqlite3_open_v2(
+                ":memory:\0".as_ptr() as _,
+                &mut ptr,
+                ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE,
+                null(),
+            )
+        })?;
+        let db = unsafe { Connection::from_ptr(ptr) };
+        let deadline = Instant::now() + Duration::from_secs(60);
+        let mut stmt = db.prepare("SELECT 1")?;
+        stmt.with_deadline(deadline);
+        match stmt.query_row((), |_| Ok(())) {
+            Err(Error::Sqlite(ffi::SQLITE_MISUSE, _)) => (),
+            x => panic!("expected SQLITE_MISUSE, got {:?}", x),
+        }
+        db.remove_progress_handler();
+        stmt.query_row((), |_| Ok(()))?;
+        drop(stmt);
+        let managed = || super::handlers().iter().any(|(x, _)| *x == ptr as usize);
+        assert!(managed());
+        // The connection is forgotten when it is closed, even by other means.
+        Error::from_sqlite(unsafe { ffi::sqlite3_close(ptr) })?;
+        assert!(!managed());
+        Ok(())
+    }
+}
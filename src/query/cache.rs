@@ -0,0 +1,199 @@
+use super::{PrepareFlags, Statement};
+use crate::{sqlite3_match_version, types::*, Connection};
+use std::{
+    cell::RefCell,
+    ops::{Deref, DerefMut},
+};
+
+/// A cache of prepared statements for a single connection.
+///
+/// Preparing a statement requires SQLite to parse the SQL and plan the query, which can
+/// dominate the cost of running simple queries. StatementCache keeps recently used
+/// statements prepared so that running the same SQL again skips this work. When the cache
+/// exceeds its capacity, the least recently used statement is finalized.
+///
+/// Statements are borrowed from the cache using [prepare](StatementCache::prepare), and
+/// are returned to the cache when the [CachedStatement] is dropped. If the database schema
+/// changes, the cache is flushed the next time a statement is returned to it.
+///
+/// Because [Connection] is a borrowed view of the underlying SQLite connection, it cannot
+/// hold a cache itself. The cache is instead owned by the caller, for example in the struct
+/// implementing a virtual table. The cache borrows the connection, so the
+/// [Database](crate::Database) cannot be closed while the cache exists, and the cached
+/// statements are finalized when the cache is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use sqlite3_ext::{query::StatementCache, *};
+///
+/// fn lookup(cache: &StatementCache, ids: &[i64]) -> Result<Vec<String>> {
+///     ids.iter()
+///         .map(|id| {
+///             cache
+///                 .prepare("SELECT name FROM users WHERE id = ?")?
+///                 .query_row([*id], |r| Ok(r[0].get_str()?.to_owned()))
+///         })
+///         .collect()
+/// }
+/// ```
+pub struct StatementCache<'db> {
+    db: &'db Connection,
+    capacity: usize,
+    statements: RefCell<Vec<(String, Statement<'db>)>>,
+}
+
+impl<'db> StatementCache<'db> {
+    /// Create a cache which holds at most capacity statements prepared on the connection.
+    /// A capacity of 0 disables caching.
+    pub fn new(db: &'db Connection, capacity: usize) -> Self {
+        StatementCache {
+            db,
+            capacity,
+            statements: RefCell::new(Vec::with_capacity(capacity)),
+        }
+    }
+
+    /// Returns the maximum number of statements which will be cached.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the maximum number of statements which will be cached. If there are
+    /// currently more statements in the cache, the least recently used ones are finalized.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        let statements = self.statements.get_mut();
+        let excess = statements.len().saturating_sub(capacity);
+        statements.drain(..excess);
+    }
+
+    /// Returns the number of statements currently in the cache. Statements which are
+    /// borrowed from the cache are not included.
+    pub fn len(&self) -> usize {
+        self.statements.borrow().len()
+    }
+
+    /// Returns true if there are no statements currently in the cache.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Finalize all statements in the cache.
+    pub fn flush(&self) {
+        self.statements.borrow_mut().clear();
+    }
+
+    /// Borrow a prepared statement for the SQL from the cache, or prepare it if it is not
+    /// in the cache. The SQL must contain exactly one statement.
+    ///
+    /// The same SQL may be borrowed multiple times simultaneously, in which case a
+    /// separate statement is prepared for each borrow. Statements are prepared with
+    /// [PrepareFlags::PERSISTENT] when it is supported.
+    pub fn prepare(&self, sql: &str) -> Result<CachedStatement<'_, 'db>> {
+        let cached = {
+            let mut statements = self.statements.borrow_mut();
+            statements
+                .iter()
+                .rposition(|(key, _)| key == sql)
+                .map(|pos| statements.remove(pos))
+        };
+        let (sql, stmt) = match cached {
+            Some(x) => x,
+            None => {
+                let flags = sqlite3_match_version! {
+                    3_020_000 => PrepareFlags::PERSISTENT,
+                    _ => PrepareFlags::empty(),
+                };
+                (sql.to_owned(), self.db.prepare_with_flags(sql, flags)?)
+            }
+        };
+        Ok(CachedStatement {
+            cache: self,
+            sql,
+            stmt: Some(stmt),
+        })
+    }
+
+    fn check_in(&self, sql: String, mut stmt: Statement<'db>) {
+        if stmt.reset().is_err() {
+            return;
+        }
+        stmt.deadline = None;
+        let mut statements = self.statements.borrow_mut();
+        // Statements are recompiled when the schema changes. Since the other statements
+        // will need to be recompiled as well, we flush the entire cache. The counter is not
+        // available before SQLite 3.20.0, in which case the statements are kept.
+        let reprepared = sqlite3_match_version! {
+            3_020_000 => stmt.status(super::StmtStatus::Reprepare, true).unwrap_or(0) > 0,
+            _ => false,
+        };
+        if reprepared {
+            statements.clear();
+            return;
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        if statements.len() >= self.capacity {
+            statements.remove(0);
+        }
+        statements.push((sql, stmt));
+    }
+}
+
+impl std::fmt::Debug for StatementCache<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("StatementCache")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A prepared statement borrowed from a [StatementCache].
+///
+/// This struct derefs to [Statement]. When it is dropped, the statement is reset and
+/// returned to the cache.
+pub struct CachedStatement<'cache, 'db> {
+    cache: &'cache StatementCache<'db>,
+    sql: String,
+    stmt: Option<Statement<'db>>,
+}
+
+impl CachedStatement<'_, '_> {
+    /// Finalize the statement instead of returning it to the cache.
+    pub fn discard(mut self) {
+        self.stmt = None;
+    }
+}
+
+impl<'db> Deref for CachedStatement<'_, 'db> {
+    type Target = Statement<'db>;
+
+    fn deref(&self) -> &Statement<'db> {
+        self.stmt.as_ref().unwrap()
+    }
+}
+
+impl<'db> DerefMut for CachedStatement<'_, 'db> {
+    fn deref_mut(&mut self) -> &mut Statement<'db> {
+        self.stmt.as_mut().unwrap()
+    }
+}
+
+impl std::fmt::Debug for CachedStatement<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("CachedStatement")
+            .field("sql", &self.sql)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for CachedStatement<'_, '_> {
+    fn drop(&mut self) {
+        if let Some(stmt) = self.stmt.take() {
+            self.cache.check_in(std::mem::take(&mut self.sql), stmt);
+        }
+    }
+}
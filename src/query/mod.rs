@@ -2,20 +2,130 @@
 //!
 //! The main entry points into this module are [Connection::prepare], [Connection::execute],
 //! and [Connection::query_row].
-use super::{ffi, iterator::*, sqlite3_match_version, types::*, value::*, Connection};
+use super::{
+    ffi, iterator::*, sqlite3_match_version, sqlite3_require_version, types::*, value::*,
+    Connection, FromRow,
+};
+pub use cache::*;
 pub use params::*;
 use std::{
+    cell::RefCell,
+    collections::HashMap,
     convert::{AsMut, AsRef},
     ffi::{CStr, CString},
     mem::MaybeUninit,
     num::NonZeroI32,
     ops::{Index, IndexMut},
     slice, str,
+    time::Instant,
 };
 
+mod cache;
 mod params;
 mod test;
 
+/// A performance counter for a prepared statement. See [Statement::status].
+///
+/// For more information, see [the SQLite documentation](https://www.sqlite.org/c3ref/c_stmtstatus_counter.html).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum StmtStatus {
+    /// The number of times that SQLite has stepped forward in a table as part of a full
+    /// table scan.
+    FullscanStep,
+    /// The number of sort operations that have occurred.
+    Sort,
+    /// The number of rows inserted into transient indices that were created automatically
+    /// in order to help joins run faster.
+    ///
+    /// Requires SQLite 3.7.0.
+    AutoIndex,
+    /// The number of virtual machine operations executed by the prepared statement.
+    ///
+    /// Requires SQLite 3.10.0.
+    VmStep,
+    /// The number of times that the prepared statement has been automatically regenerated
+    /// due to schema changes or changes to bound parameters that might affect the query
+    /// plan.
+    ///
+    /// Requires SQLite 3.20.0.
+    Reprepare,
+    /// The number of times that the prepared statement has been run.
+    ///
+    /// Requires SQLite 3.20.0.
+    Run,
+    /// The number of times that a join step was bypassed because a Bloom filter returned
+    /// not-found.
+    ///
+    /// Requires SQLite 3.38.0.
+    FilterMiss,
+    /// The number of times that a Bloom filter returned a find, and thus the join step had
+    /// to be processed as normal.
+    ///
+    /// Requires SQLite 3.38.0.
+    FilterHit,
+    /// The approximate number of bytes of heap memory used to store the prepared statement.
+    ///
+    /// Requires SQLite 3.20.0.
+    MemUsed,
+}
+
+impl StmtStatus {
+    // SQLite does not validate the counter, so using one which the running version of
+    // SQLite does not have would access memory outside of the statement's counters.
+    fn to_sqlite(self) -> Result<i32> {
+        match self {
+            StmtStatus::FullscanStep => Ok(ffi::SQLITE_STMTSTATUS_FULLSCAN_STEP),
+            StmtStatus::Sort => Ok(ffi::SQLITE_STMTSTATUS_SORT),
+            StmtStatus::AutoIndex => {
+                sqlite3_require_version!(3_007_000, Ok(ffi::SQLITE_STMTSTATUS_AUTOINDEX))
+            }
+            StmtStatus::VmStep => {
+                sqlite3_require_version!(3_010_000, Ok(ffi::SQLITE_STMTSTATUS_VM_STEP))
+            }
+            StmtStatus::Reprepare => {
+                sqlite3_require_version!(3_020_000, Ok(ffi::SQLITE_STMTSTATUS_REPREPARE))
+            }
+            StmtStatus::Run => sqlite3_require_version!(3_020_000, Ok(ffi::SQLITE_STMTSTATUS_RUN)),
+            StmtStatus::FilterMiss => {
+                sqlite3_require_version!(3_038_000, Ok(ffi::SQLITE_STMTSTATUS_FILTER_MISS))
+            }
+            StmtStatus::FilterHit => {
+                sqlite3_require_version!(3_038_000, Ok(ffi::SQLITE_STMTSTATUS_FILTER_HIT))
+            }
+            StmtStatus::MemUsed => {
+                sqlite3_require_version!(3_020_000, Ok(ffi::SQLITE_STMTSTATUS_MEMUSED))
+            }
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// These are the flags that can be passed to [Connection::prepare_with_flags].
+    #[repr(transparent)]
+    pub struct PrepareFlags: u32 {
+        /// The prepared statement will be retained for a long time and probably reused
+        /// many times. This is a hint to the query planner to avoid lookaside memory.
+        const PERSISTENT = ffi::SQLITE_PREPARE_PERSISTENT as _;
+        /// No-op, retained for compatibility.
+        const NORMALIZE = ffi::SQLITE_PREPARE_NORMALIZE as _;
+        /// Preparing the statement will fail with an error if it uses any virtual
+        /// tables.
+        const NO_VTAB = ffi::SQLITE_PREPARE_NO_VTAB as _;
+    }
+}
+
+/// Describes whether a prepared statement is an EXPLAIN statement. See
+/// [Statement::explain_mode].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ExplainMode {
+    /// The statement is an ordinary statement.
+    Normal,
+    /// The statement is an EXPLAIN statement.
+    Explain,
+    /// The statement is an EXPLAIN QUERY PLAN statement.
+    QueryPlan,
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 enum QueryState {
     Ready,
@@ -74,21 +184,53 @@ enum QueryState {
 ///     Ok(results)
 /// }
 /// ```
-pub struct Statement {
+pub struct Statement<'db> {
+    db: &'db Connection,
     base: *mut ffi::sqlite3_stmt,
     state: QueryState,
     // We allocate column objects for all columns so that they can be returned by our Index
     // implementation. It's possible to skip this if we add a lifetime parameter to Column to
     // prevent pointer aliasing, but then we can't use Index and IndexMut.
     columns: Box<[Column]>,
+    // Lazily-built map from column name to index. SQLite may recompile the statement at the
+    // start of each execution, which can change the column names, so the map is cleared
+    // whenever a new execution starts.
+    column_names: RefCell<Option<HashMap<String, usize>>>,
+    deadline: Option<Instant>,
 }
 
 impl Connection {
     /// Prepare some SQL for execution. This method will return the prepared statement and
     /// a slice containing the portion of the original input which was after the first SQL
     /// statement.
-    pub fn prepare_first<'a>(&self, sql: &'a str) -> Result<(Option<Statement>, &'a str)> {
-        const FLAGS: u32 = 0;
+    pub fn prepare_first<'a>(&self, sql: &'a str) -> Result<(Option<Statement<'_>>, &'a str)> {
+        self.prepare_first_with_flags(sql, PrepareFlags::empty())
+    }
+
+    /// Prepare some SQL for execution, with the given flags. This method is otherwise
+    /// identical to [prepare_first](Connection::prepare_first).
+    ///
+    /// Requires SQLite 3.20.0, unless no flags are given. [PrepareFlags::NO_VTAB] requires
+    /// SQLite 3.29.0.
+    pub fn prepare_first_with_flags<'a>(
+        &self,
+        sql: &'a str,
+        flags: PrepareFlags,
+    ) -> Result<(Option<Statement<'_>>, &'a str)> {
+        if !flags.is_empty() {
+            let required = if flags.contains(PrepareFlags::NO_VTAB) {
+                3_029_000
+            } else {
+                3_020_000
+            };
+            let supported = sqlite3_match_version! {
+                3_020_000 => crate::SQLITE_VERSION.as_i32() >= required,
+                _ => false,
+            };
+            if !supported {
+                return Err(Error::VersionNotSatisfied(required));
+            }
+        }
         let guard = self.lock();
         let mut ret = MaybeUninit::uninit();
         let mut rest = MaybeUninit::uninit();
@@ -99,7 +241,7 @@ impl Connection {
                         self.as_mut_ptr(),
                         sql.as_ptr() as _,
                         sql.len() as _,
-                        FLAGS,
+                        flags.bits,
                         ret.as_mut_ptr(),
                         rest.as_mut_ptr(),
                     ),
@@ -122,9 +264,12 @@ impl Connection {
             let len = unsafe { ffi::sqlite3_column_count(stmt) as usize };
             let columns = (0..len).map(|i| Column::new(stmt, i)).collect();
             Some(Statement {
+                db: self,
                 base: stmt,
                 state: QueryState::Ready,
                 columns,
+                column_names: RefCell::new(None),
+                deadline: None,
             })
         };
 
@@ -136,13 +281,81 @@ impl Connection {
 
     /// Prepare some SQL for execution. This method will return Err([SQLITE_MISUSE]) if the
     /// input string does not contain any SQL statements.
-    pub fn prepare(&self, sql: &str) -> Result<Statement> {
+    pub fn prepare(&self, sql: &str) -> Result<Statement<'_>> {
         self.prepare_first(sql)?.0.ok_or(SQLITE_MISUSE)
     }
 
+    /// Prepare some SQL for execution, with the given flags. This method will return
+    /// Err([SQLITE_MISUSE]) if the input string does not contain any SQL statements.
+    ///
+    /// Requires SQLite 3.20.0, unless no flags are given. [PrepareFlags::NO_VTAB] requires
+    /// SQLite 3.29.0.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use sqlite3_ext::{query::PrepareFlags, *};
+    ///
+    /// fn count_rows(db: &Connection, table: &str) -> Result<i64> {
+    ///     // Refuse to read from virtual tables, which could recurse into this function.
+    ///     db.prepare_with_flags(
+    ///         &format!("SELECT COUNT(*) FROM \"{}\"", table.replace('"', "\"\"")),
+    ///         PrepareFlags::NO_VTAB,
+    ///     )?
+    ///     .query_row((), |r| Ok(r[0].get_i64()))
+    /// }
+    /// ```
+    pub fn prepare_with_flags(&self, sql: &str, flags: PrepareFlags) -> Result<Statement<'_>> {
+        self.prepare_first_with_flags(sql, flags)?
+            .0
+            .ok_or(SQLITE_MISUSE)
+    }
+
+    /// Returns an iterator which prepares each of the statements in the SQL, in order.
+    /// Empty statements and comments are skipped.
+    ///
+    /// Each statement is prepared when the iterator advances, so statements may refer to
+    /// tables created by earlier statements, as long as those statements are executed
+    /// first.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use sqlite3_ext::*;
+    ///
+    /// fn migrate(db: &Connection, script: &str) -> Result<()> {
+    ///     let mut statements = db.statements(script);
+    ///     while let Some(mut stmt) = statements.next()? {
+    ///         if stmt.column_count() > 0 {
+    ///             return Err(Error::Module("migrations cannot return rows".to_owned()));
+    ///         }
+    ///         stmt.execute(())?;
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn statements<'a>(&self, sql: &'a str) -> Statements<'_, 'a> {
+        Statements {
+            db: self,
+            rest: sql,
+        }
+    }
+
+    /// Execute all of the statements in the SQL, in order. Any rows returned by the
+    /// statements are ignored. Execution stops at the first statement which fails.
+    ///
+    /// This method is equivalent to sqlite3_exec without a callback.
+    pub fn execute_batch(&self, sql: &str) -> Result<()> {
+        let mut statements = self.statements(sql);
+        while let Some(mut stmt) = statements.next()? {
+            while stmt.next()?.is_some() {}
+        }
+        Ok(())
+    }
+
     /// Convenience method to prepare a query and bind it with values. See
     /// [Statement::query].
-    pub fn query<P>(&self, sql: &str, params: P) -> Result<Statement>
+    pub fn query<P>(&self, sql: &str, params: P) -> Result<Statement<'_>>
     where
         P: Params,
     {
@@ -156,11 +369,34 @@ impl Connection {
     pub fn query_row<P, R, F>(&self, sql: &str, params: P, f: F) -> Result<R>
     where
         P: Params,
-        F: FnOnce(&mut QueryResult) -> Result<R>,
+        F: FnOnce(&mut QueryResult<'_>) -> Result<R>,
     {
         self.prepare(sql)?.query_row(params, f)
     }
 
+    /// Execute a query which is expected to return only a single row, and convert it using
+    /// [FromRow::from_query_result]. See [Statement::query_row].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use sqlite3_ext::*;
+    ///
+    /// #[derive(FromRow)]
+    /// struct User {
+    ///     id: i64,
+    ///     #[sqlite(rename = "display_name")]
+    ///     name: String,
+    /// }
+    ///
+    /// fn find_user(db: &Connection, id: i64) -> Result<User> {
+    ///     db.query_one("SELECT id, display_name FROM users WHERE id = ?", [id])
+    /// }
+    /// ```
+    pub fn query_one<T: FromRow, P: Params>(&self, sql: &str, params: P) -> Result<T> {
+        self.prepare(sql)?.query_row(params, T::from_query_result)
+    }
+
     /// Convenience method for `self.prepare(sql)?.execute(params)`. See [Statement::execute].
     pub fn execute<P: Params>(&self, sql: &str, params: P) -> Result<i64> {
         self.prepare(sql)?.execute(params)
@@ -172,7 +408,7 @@ impl Connection {
     }
 }
 
-impl Statement {
+impl<'db> Statement<'db> {
     /// Return the underlying sqlite3_stmt pointer.
     ///
     /// # Safety
@@ -206,7 +442,7 @@ impl Statement {
     pub fn query_row<P, R, F>(&mut self, params: P, f: F) -> Result<R>
     where
         P: Params,
-        F: FnOnce(&mut QueryResult) -> Result<R>,
+        F: FnOnce(&mut QueryResult<'_>) -> Result<R>,
     {
         let res = self.query(params)?.next().map(|o| o.map(|row| f(row)));
         // Always reset the query after using, although we prioritize a query failure
@@ -222,6 +458,66 @@ impl Statement {
         }
     }
 
+    /// Bind the provided parameters to the query, and return an iterator which converts
+    /// each row using [FromRow::from_query_result].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use sqlite3_ext::*;
+    ///
+    /// #[derive(FromRow)]
+    /// struct Page {
+    ///     id: i64,
+    ///     name: String,
+    /// }
+    ///
+    /// fn pages(conn: &Connection, user_id: i64) -> Result<Vec<Page>> {
+    ///     conn.prepare("SELECT id, name FROM pages WHERE owner_id = ?")?
+    ///         .query_map([user_id])?
+    ///         .collect()
+    /// }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn query_map<T: FromRow, P: Params>(
+        &mut self,
+        params: P,
+    ) -> Result<Map<'_, Self, fn(&mut QueryResult<'db>) -> Result<T>>> {
+        Ok(self.query(params)?.map(T::from_query_result as _))
+    }
+
+    /// Returns an iterator over the remaining rows of the query, which copies each row into
+    /// a Vec of owned values. Unlike [map](FallibleIteratorMut::map), this is a standard
+    /// [Iterator], and the rows can be kept after the iterator advances.
+    ///
+    /// The iterator ends after the first error.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use sqlite3_ext::*;
+    ///
+    /// fn dump(conn: &Connection) -> Result<()> {
+    ///     let mut stmt = conn.query("SELECT * FROM pages", ())?;
+    ///     for row in stmt.rows_owned() {
+    ///         println!("{:?}", row?);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn rows_owned(&mut self) -> RowsOwned<'_, 'db> {
+        RowsOwned {
+            stmt: self,
+            failed: false,
+        }
+    }
+
+    /// Collect the remaining rows of the query into a Vec of owned values. See
+    /// [rows_owned](Self::rows_owned).
+    pub fn collect_values(&mut self) -> Result<Vec<Vec<Value>>> {
+        self.rows_owned().collect()
+    }
+
     /// Execute a query that is expected to return no results (such as an INSERT, UPDATE, or
     /// DELETE).
     ///
@@ -231,7 +527,7 @@ impl Statement {
     /// If you are not storing this Statement for later reuse, [Connection::execute] is a shortcut
     /// for this method.
     pub fn execute<P: Params>(&mut self, params: P) -> Result<i64> {
-        let db = unsafe { self.db() }.lock();
+        let db = self.db.lock();
 
         let res = self.query(params)?.next().map(|r| r.is_some());
         // Always reset the query after using, although we prioritize a query failure
@@ -258,7 +554,7 @@ impl Statement {
     /// other verifications that the executed statement is actually an INSERT. If this Statement is
     /// not an INSERT, the return value of this function is meaningless.
     pub fn insert<P: Params>(&mut self, params: P) -> Result<i64> {
-        let db = unsafe { self.db() }.lock();
+        let db = self.db.lock();
         let res = self.query(params)?.next().map(|r| r.is_some());
         // Always reset the query after using, although we prioritize a query failure
         // in the return value.
@@ -308,15 +604,187 @@ impl Statement {
         })
     }
 
+    /// Return the position of the named parameter, or fail with [SQLITE_RANGE] if there is
+    /// no such parameter. The error message includes the name of the missing parameter.
+    ///
+    /// If the name does not begin with one of the parameter prefixes (`:`, `@`, or `$`),
+    /// then `:name`, `@name`, and `$name` are tried, in that order.
+    pub fn find_parameter(&self, name: &str) -> Result<NonZeroI32> {
+        let pos = if name.starts_with([':', '@', '$', '?']) {
+            self.parameter_position(name)
+        } else {
+            [':', '@', '$']
+                .iter()
+                .find_map(|prefix| self.parameter_position(format!("{}{}", prefix, name)))
+        };
+        pos.ok_or_else(|| {
+            Error::Sqlite(
+                ffi::SQLITE_RANGE,
+                Some(format!("no such parameter: {}", name)),
+            )
+        })
+    }
+
     /// Returns the number of columns in the result set returned by this query.
     pub fn column_count(&self) -> usize {
         unsafe { ffi::sqlite3_column_count(self.base) as _ }
     }
 
+    /// Returns the index of the result column with the given name, or None if there is no
+    /// such column. Names are compared exactly, and if multiple columns have the same name,
+    /// the first one is returned. See [Column::name] for information about column names.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.column_names
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                self.columns
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .filter_map(|(i, c)| Some((c.name().ok()?.to_owned(), i)))
+                    .collect()
+            })
+            .get(name)
+            .copied()
+    }
+
+    /// Returns the number of columns in the current result. This is 0 if the query is not
+    /// currently positioned on a row.
+    pub fn data_count(&self) -> usize {
+        unsafe { ffi::sqlite3_data_count(self.base) as _ }
+    }
+
+    /// Returns the text of the prepared statement with bound parameters expanded.
+    ///
+    /// Requires SQLite 3.14.0.
+    pub fn expanded_sql(&self) -> Result<String> {
+        sqlite3_require_version!(3_014_000, unsafe {
+            let ptr = ffi::sqlite3_expanded_sql(self.base);
+            if ptr.is_null() {
+                return Err(SQLITE_NOMEM);
+            }
+            let ret = CStr::from_ptr(ptr).to_str().map(String::from);
+            ffi::sqlite3_free(ptr as _);
+            Ok(ret?)
+        })
+    }
+
+    /// Returns the normalized text of the prepared statement, with literals replaced by
+    /// parameters and whitespace and keyword case made consistent.
+    ///
+    /// Requires SQLite 3.27.0, compiled with SQLITE_ENABLE_NORMALIZE. This method is not
+    /// available when statically linking to SQLite, and always returns an error in that
+    /// case.
+    pub fn normalized_sql(&self) -> Result<&str> {
+        #[cfg(not(feature = "static"))]
+        {
+            sqlite3_require_version!(3_027_000, unsafe {
+                if !ffi::has_normalized_sql() {
+                    return Err(Error::Sqlite(
+                        ffi::SQLITE_ERROR,
+                        Some("sqlite3_normalized_sql is not available".to_owned()),
+                    ));
+                }
+                let ptr = ffi::sqlite3_normalized_sql(self.base);
+                if ptr.is_null() {
+                    return Err(SQLITE_NOMEM);
+                }
+                Ok(CStr::from_ptr(ptr).to_str()?)
+            })
+        }
+        #[cfg(feature = "static")]
+        Err(Error::Sqlite(
+            ffi::SQLITE_ERROR,
+            Some("sqlite3_normalized_sql is not available".to_owned()),
+        ))
+    }
+
+    /// Returns true if the prepared statement makes no direct changes to the content of
+    /// the database file.
+    ///
+    /// Requires SQLite 3.7.4.
+    pub fn readonly(&self) -> Result<bool> {
+        sqlite3_require_version!(3_007_004, unsafe {
+            Ok(ffi::sqlite3_stmt_readonly(self.base) != 0)
+        })
+    }
+
+    /// Returns true if the prepared statement has been stepped at least once but has not
+    /// run to completion or been reset.
+    ///
+    /// Requires SQLite 3.7.10.
+    pub fn busy(&self) -> Result<bool> {
+        sqlite3_require_version!(3_007_010, unsafe {
+            Ok(ffi::sqlite3_stmt_busy(self.base) != 0)
+        })
+    }
+
+    /// Returns whether the prepared statement is an EXPLAIN or EXPLAIN QUERY PLAN
+    /// statement.
+    ///
+    /// Requires SQLite 3.28.0.
+    pub fn explain_mode(&self) -> Result<ExplainMode> {
+        sqlite3_require_version!(3_028_000, unsafe {
+            match ffi::sqlite3_stmt_isexplain(self.base) {
+                1 => Ok(ExplainMode::Explain),
+                2 => Ok(ExplainMode::QueryPlan),
+                _ => Ok(ExplainMode::Normal),
+            }
+        })
+    }
+
+    /// Returns the value of a performance counter for this statement. If reset is true,
+    /// the counter is reset to 0 after it is retrieved.
+    ///
+    /// Returns [Error::VersionNotSatisfied] if the running version of SQLite does not
+    /// support the counter.
+    pub fn status(&self, op: StmtStatus, reset: bool) -> Result<i32> {
+        let op = op.to_sqlite()?;
+        Ok(unsafe { ffi::sqlite3_stmt_status(self.base, op, reset as _) })
+    }
+
+    /// Limit the time spent running this statement. Any step of the statement which is
+    /// still running at the deadline is interrupted and fails with
+    /// [Error::DeadlineExceeded], as does any step started after the deadline has passed.
+    /// The deadline applies to all later queries using this statement, until it is changed
+    /// or removed with [clear_deadline](Self::clear_deadline).
+    ///
+    /// The deadline is enforced by a progress handler which is installed on the connection
+    /// only while the statement is being stepped. Afterwards, the handler registered with
+    /// [Connection::set_progress_handler] is restored. SQLite does not provide a way to
+    /// retrieve a progress handler installed by other means, for example by the application
+    /// which loaded an extension, so stepping fails with SQLITE_MISUSE rather than removing
+    /// it, unless the connection was opened by [Database](crate::Database) or its progress
+    /// handler has been set or removed with [Connection::set_progress_handler] or
+    /// [Connection::remove_progress_handler].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use sqlite3_ext::*;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// fn count_with_timeout(db: &Connection, timeout: Duration) -> Result<i64> {
+    ///     db.prepare("SELECT COUNT(*) FROM big_table")?
+    ///         .with_deadline(Instant::now() + timeout)
+    ///         .query_row((), |r| Ok(r[0].get_i64()))
+    /// }
+    /// ```
+    pub fn with_deadline(&mut self, deadline: Instant) -> &mut Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Remove the deadline set with [with_deadline](Self::with_deadline).
+    pub fn clear_deadline(&mut self) -> &mut Self {
+        self.deadline = None;
+        self
+    }
+
     /// Returns the current result, without advancing the cursor. This method returns `None` if the
     /// query has already run to completion, or if the query has not been started using
     /// [query](Self::query).
-    pub fn current_result(&self) -> Option<&QueryResult> {
+    pub fn current_result(&self) -> Option<&QueryResult<'db>> {
         match self.state {
             QueryState::Active => Some(QueryResult::from_statement(self)),
             _ => None,
@@ -324,7 +792,7 @@ impl Statement {
     }
 
     /// Mutable version of [current_result](Self::current_result).
-    pub fn current_result_mut(&mut self) -> Option<&mut QueryResult> {
+    pub fn current_result_mut(&mut self) -> Option<&mut QueryResult<'db>> {
         match self.state {
             QueryState::Active => Some(QueryResult::from_statement_mut(self)),
             _ => None,
@@ -332,14 +800,8 @@ impl Statement {
     }
 
     /// Returns a handle to the Connection associated with this statement.
-    ///
-    /// # Safety
-    ///
-    /// The returned reference's lifetime is not tied to the lifetime of this Statement. It
-    /// is the responsibility of the caller to ensure that the Connection reference is not
-    /// improperly used.
-    pub unsafe fn db<'a>(&self) -> &'a Connection {
-        Connection::from_ptr(ffi::sqlite3_db_handle(self.base))
+    pub fn db(&self) -> &'db Connection {
+        self.db
     }
 
     fn reset(&mut self) -> Result<()> {
@@ -352,15 +814,21 @@ impl Statement {
     }
 }
 
-impl FallibleIteratorMut for Statement {
-    type Item = QueryResult;
+impl<'db> FallibleIteratorMut for Statement<'db> {
+    type Item = QueryResult<'db>;
     type Error = Error;
 
     fn next(&mut self) -> Result<Option<&mut Self::Item>> {
         match self.state {
             QueryState::Ready | QueryState::Active => unsafe {
-                let guard = self.db().lock();
-                let rc = ffi::sqlite3_step(self.base);
+                if self.state == QueryState::Ready {
+                    *self.column_names.get_mut() = None;
+                }
+                let guard = self.db.lock();
+                let rc = match self.deadline {
+                    Some(at) => crate::progress::step_with_deadline(self.db, self.base, at)?,
+                    None => ffi::sqlite3_step(self.base),
+                };
                 Error::from_sqlite_desc(rc, guard)?;
                 match rc {
                     ffi::SQLITE_DONE => {
@@ -368,6 +836,13 @@ impl FallibleIteratorMut for Statement {
                         Ok(None)
                     }
                     ffi::SQLITE_ROW => {
+                        // The statement may have been recompiled after a schema change,
+                        // which can change the number of result columns.
+                        let len = ffi::sqlite3_column_count(self.base) as usize;
+                        if len != self.columns.len() {
+                            self.columns = (0..len).map(|i| Column::new(self.base, i)).collect();
+                            *self.column_names.get_mut() = None;
+                        }
                         self.state = QueryState::Active;
                         Ok(Some(QueryResult::from_statement_mut(self)))
                     }
@@ -379,7 +854,7 @@ impl FallibleIteratorMut for Statement {
     }
 }
 
-impl std::fmt::Debug for Statement {
+impl std::fmt::Debug for Statement<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.debug_struct("Statement")
             .field("state", &self.state)
@@ -387,34 +862,123 @@ impl std::fmt::Debug for Statement {
     }
 }
 
-impl Drop for Statement {
+impl Drop for Statement<'_> {
     fn drop(&mut self) {
         unsafe { ffi::sqlite3_finalize(self.base) };
     }
 }
 
+/// An iterator over the rows of a query, as owned values. See [Statement::rows_owned].
+pub struct RowsOwned<'stmt, 'db> {
+    stmt: &'stmt mut Statement<'db>,
+    failed: bool,
+}
+
+impl Iterator for RowsOwned<'_, '_> {
+    type Item = Result<Vec<Value>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let ret = self.stmt.next().and_then(|row| {
+            row.map(|row| (0..row.len()).map(|i| row[i].to_owned()).collect())
+                .transpose()
+        });
+        self.failed = ret.is_err();
+        ret.transpose()
+    }
+}
+
+impl std::fmt::Debug for RowsOwned<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("RowsOwned")
+            .field("stmt", &self.stmt)
+            .field("failed", &self.failed)
+            .finish()
+    }
+}
+
+/// An iterator over the statements in an SQL string. See [Connection::statements].
+pub struct Statements<'db, 'sql> {
+    db: &'db Connection,
+    rest: &'sql str,
+}
+
+impl<'db> FallibleIterator for Statements<'db, '_> {
+    type Item = Statement<'db>;
+    type Error = Error;
+
+    fn next(&mut self) -> Result<Option<Statement<'db>>> {
+        while !self.rest.is_empty() {
+            let (stmt, rest) = match self.db.prepare_first(self.rest) {
+                Ok(x) => x,
+                Err(e) => {
+                    self.rest = "";
+                    return Err(e);
+                }
+            };
+            // SQLite always consumes input unless it is at the end of the string, but
+            // guard against looping forever.
+            self.rest = if rest.len() < self.rest.len() {
+                rest
+            } else {
+                ""
+            };
+            if stmt.is_some() {
+                return Ok(stmt);
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl std::fmt::Debug for Statements<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Statements")
+            .field("rest", &self.rest)
+            .finish_non_exhaustive()
+    }
+}
+
 /// A row returned from a query.
 #[repr(transparent)]
-pub struct QueryResult {
-    stmt: Statement,
+pub struct QueryResult<'db> {
+    stmt: Statement<'db>,
 }
 
-impl QueryResult {
-    fn from_statement(stmt: &Statement) -> &Self {
-        unsafe { &*(stmt as *const Statement as *const Self) }
+impl<'db> QueryResult<'db> {
+    fn from_statement<'a>(stmt: &'a Statement<'db>) -> &'a Self {
+        unsafe { &*(stmt as *const Statement<'db> as *const Self) }
     }
 
-    fn from_statement_mut(stmt: &mut Statement) -> &mut Self {
-        unsafe { &mut *(stmt as *mut Statement as *mut Self) }
+    fn from_statement_mut<'a>(stmt: &'a mut Statement<'db>) -> &'a mut Self {
+        unsafe { &mut *(stmt as *mut Statement<'db> as *mut Self) }
     }
 
     /// Returns the number of columns in the result.
     pub fn len(&self) -> usize {
         self.stmt.column_count()
     }
+
+    /// Returns the column with the given name. This method fails if there is no such
+    /// column. See [Statement::column_index] for details on how columns are matched.
+    pub fn get_by_name(&mut self, name: &str) -> Result<&mut Column> {
+        match self.stmt.column_index(name) {
+            Some(i) => Ok(&mut self.stmt.columns[i]),
+            None => Err(Error::Sqlite(
+                ffi::SQLITE_ERROR,
+                Some(format!("no such column: {}", name)),
+            )),
+        }
+    }
+
+    pub(crate) fn columns_mut(&mut self) -> &mut [Column] {
+        &mut self.stmt.columns
+    }
 }
 
-impl Index<usize> for QueryResult {
+impl Index<usize> for QueryResult<'_> {
     type Output = Column;
 
     fn index(&self, index: usize) -> &Self::Output {
@@ -422,13 +986,13 @@ impl Index<usize> for QueryResult {
     }
 }
 
-impl IndexMut<usize> for QueryResult {
+impl IndexMut<usize> for QueryResult<'_> {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         &mut self.stmt.columns[index]
     }
 }
 
-impl std::fmt::Debug for QueryResult {
+impl std::fmt::Debug for QueryResult<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut dt = f.debug_tuple("QueryResult");
         for i in 0..self.len() {
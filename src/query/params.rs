@@ -1,6 +1,7 @@
 use super::Statement;
 use crate::{ffi, sqlite3_match_version, sqlite3_require_version, types::*, value::*};
 use sealed::sealed;
+use std::collections::{BTreeMap, HashMap};
 
 /// Create a [Params] with values of mixed types.
 ///
@@ -65,6 +66,12 @@ macro_rules! params {
 /// Named parameters are implemented by using a tuple of `("name", value)`, and can be in any
 /// order. See [params!] for an example.
 ///
+/// Named parameters can also be provided from a `HashMap` or `BTreeMap`, from a slice of
+/// `(&str, &dyn ToParam)` or `(&str, &dyn ToSql)`, or from a struct using
+/// [derive(Params)](macro@crate::Params). In these cases, the names may omit the prefix
+/// character, and it is an error if any name does not match a parameter. See
+/// [Statement::find_parameter].
+///
 /// # Using a closure
 ///
 /// If you are dynamically creating SQL queries and need to dynamically bind parameters to
@@ -128,14 +135,61 @@ impl Params for &mut [&mut ValueRef] {
     }
 }
 
+impl<K: AsRef<str>, V: ToParam, S> Params for HashMap<K, V, S> {
+    fn bind_params(self, stmt: &mut Statement) -> Result<()> {
+        for (name, val) in self {
+            let pos = stmt.find_parameter(name.as_ref())?;
+            val.bind_param(stmt, pos.get())?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: AsRef<str>, V: ToParam> Params for BTreeMap<K, V> {
+    fn bind_params(self, stmt: &mut Statement) -> Result<()> {
+        for (name, val) in self {
+            let pos = stmt.find_parameter(name.as_ref())?;
+            val.bind_param(stmt, pos.get())?;
+        }
+        Ok(())
+    }
+}
+
+impl Params for &[(&str, &dyn ToParam)] {
+    fn bind_params(self, stmt: &mut Statement) -> Result<()> {
+        for (name, val) in self {
+            let pos = stmt.find_parameter(name)?;
+            val.bind_param(stmt, pos.get())?;
+        }
+        Ok(())
+    }
+}
+
+impl Params for &[(&str, &dyn ToSql)] {
+    fn bind_params(self, stmt: &mut Statement) -> Result<()> {
+        for (name, val) in self {
+            let pos = stmt.find_parameter(name)?;
+            val.bind_sql(stmt, pos.get())?;
+        }
+        Ok(())
+    }
+}
+
 /// Trait for types which can be passed into SQLite queries as parameters.
-#[sealed]
+///
+/// This trait is sealed, but it is implemented for references to any type implementing
+/// [ToSql], which can be used to bind other types.
+#[sealed(pub(crate))]
 pub trait ToParam {
     /// Bind this value to the prepared Statement at the provided position.
     ///
     /// Note: the position of a named parameter can be obtained using
     /// [Statement::parameter_position].
     fn bind_param(self, stmt: &mut Statement, position: i32) -> Result<()>;
+
+    /// Bind a copy of this value. This allows values to be bound through `&dyn ToParam`.
+    #[doc(hidden)]
+    fn bind_param_ref(&self, stmt: &mut Statement, position: i32) -> Result<()>;
 }
 
 macro_rules! to_param {
@@ -148,12 +202,24 @@ macro_rules! to_param {
                 let $stmt = stmt.base;
                 Error::from_sqlite(unsafe { $impl })
             }
+
+            fn bind_param_ref(&self, stmt: &mut Statement, pos: i32) -> Result<()> {
+                self.clone().bind_param(stmt, pos)
+            }
+        }
+
+        impl ToSql for $ty {
+            fn bind_sql(&self, stmt: &mut Statement, pos: i32) -> Result<()> {
+                self.bind_param_ref(stmt, pos)
+            }
         }
     };
 }
 
 to_param!(() as (stmt, pos, _val) => ffi::sqlite3_bind_null(stmt, pos));
 to_param!(bool as (stmt, pos, val) => ffi::sqlite3_bind_int(stmt, pos, val as i32));
+to_param!(i32 as (stmt, pos, val) => ffi::sqlite3_bind_int(stmt, pos, val));
+to_param!(u32 as (stmt, pos, val) => ffi::sqlite3_bind_int64(stmt, pos, val as i64));
 to_param!(i64 as (stmt, pos, val) => ffi::sqlite3_bind_int64(stmt, pos, val));
 to_param!(f64 as (stmt, pos, val) => ffi::sqlite3_bind_double(stmt, pos, val));
 to_param!(Blob as (stmt, pos, val) => {
@@ -164,7 +230,17 @@ to_param!(Blob as (stmt, pos, val) => {
     };
     rc
 });
-to_param!(&mut ValueRef as (stmt, pos, val) => ffi::sqlite3_bind_value(stmt, pos, val.as_ptr()));
+
+#[sealed]
+impl ToParam for &mut ValueRef {
+    fn bind_param(self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        self.bind_param_ref(stmt, pos)
+    }
+
+    fn bind_param_ref(&self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        unsafe { Error::from_sqlite(ffi::sqlite3_bind_value(stmt.base, pos, self.as_ptr())) }
+    }
+}
 
 #[sealed]
 impl<'a> ToParam for &'a str {
@@ -178,6 +254,21 @@ impl<'a> ToParam for &'a str {
             }
         })
     }
+
+    fn bind_param_ref(&self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        (*self).bind_param(stmt, pos)
+    }
+}
+
+#[sealed]
+impl ToParam for String {
+    fn bind_param(self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        self.as_str().bind_param(stmt, pos)
+    }
+
+    fn bind_param_ref(&self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        self.as_str().bind_param(stmt, pos)
+    }
 }
 
 #[sealed]
@@ -185,6 +276,10 @@ impl<'a> ToParam for &'a ValueRef {
     fn bind_param(self, stmt: &mut Statement, pos: i32) -> Result<()> {
         unsafe { Error::from_sqlite(ffi::sqlite3_bind_value(stmt.base, pos, self.as_ptr())) }
     }
+
+    fn bind_param_ref(&self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        (*self).bind_param(stmt, pos)
+    }
 }
 
 #[sealed]
@@ -210,6 +305,21 @@ impl<'a> ToParam for &'a [u8] {
             })
         }
     }
+
+    fn bind_param_ref(&self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        (*self).bind_param(stmt, pos)
+    }
+}
+
+#[sealed]
+impl ToParam for Vec<u8> {
+    fn bind_param(self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        self.as_slice().bind_param(stmt, pos)
+    }
+
+    fn bind_param_ref(&self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        self.as_slice().bind_param(stmt, pos)
+    }
 }
 
 #[sealed]
@@ -217,6 +327,10 @@ impl<'a, const N: usize> ToParam for &'a [u8; N] {
     fn bind_param(self, stmt: &mut Statement, pos: i32) -> Result<()> {
         self.as_slice().bind_param(stmt, pos)
     }
+
+    fn bind_param_ref(&self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        (*self).bind_param(stmt, pos)
+    }
 }
 
 /// Sets the parameter to a dynamically typed [Value].
@@ -231,6 +345,10 @@ impl ToParam for Value {
             Value::Null => ().bind_param(stmt, pos),
         }
     }
+
+    fn bind_param_ref(&self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        self.clone().bind_param(stmt, pos)
+    }
 }
 
 /// Sets the parameter to the contained value or NULL.
@@ -245,6 +363,13 @@ where
             None => ().bind_param(stmt, pos),
         }
     }
+
+    fn bind_param_ref(&self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        match self {
+            Some(x) => x.bind_param_ref(stmt, pos),
+            None => ().bind_param(stmt, pos),
+        }
+    }
 }
 
 /// Sets the parameter to NULL with this value as an associated pointer.
@@ -262,6 +387,15 @@ impl<T: 'static> ToParam for PassedRef<T> {
             ))
         })
     }
+
+    /// A PassedRef is moved into SQLite when it is bound, so it cannot be bound by
+    /// reference.
+    fn bind_param_ref(&self, _: &mut Statement, _: i32) -> Result<()> {
+        Err(Error::Sqlite(
+            ffi::SQLITE_MISUSE,
+            Some("PassedRef cannot be bound by reference".to_owned()),
+        ))
+    }
 }
 
 /// Used to bind named parameters. Sets the parameter with the name at `self.0` to the value at
@@ -279,4 +413,109 @@ where
             None => Err(SQLITE_RANGE),
         }
     }
+
+    fn bind_param_ref(&self, _: &mut Statement, _: i32) -> Result<()> {
+        Err(Error::Sqlite(
+            ffi::SQLITE_MISUSE,
+            Some("named parameters cannot be bound by reference".to_owned()),
+        ))
+    }
+}
+
+/// Binds the referenced value. This allows parameters of different types to be stored
+/// together, for example in a slice of `(&str, &dyn ToParam)`.
+#[sealed]
+impl ToParam for &dyn ToParam {
+    fn bind_param(self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        self.bind_param_ref(stmt, pos)
+    }
+
+    fn bind_param_ref(&self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        (**self).bind_param_ref(stmt, pos)
+    }
+}
+
+/// Trait for types which can be bound as parameters to SQLite queries.
+///
+/// Unlike [ToParam], this trait can be implemented for any type. A reference to a type
+/// implementing ToSql can be used anywhere a ToParam is expected. Implementations typically
+/// convert the value to a type implementing ToParam, and bind that.
+///
+/// This trait is also implemented for the types which implement ToParam and can be bound
+/// without being consumed, so that values of different types can be bound from a slice
+/// of `(&str, &dyn ToSql)`.
+///
+/// # Examples
+///
+/// Binding a type as TEXT:
+///
+/// ```no_run
+/// use sqlite3_ext::{query::{Statement, ToParam, ToSql}, *};
+///
+/// struct Point { x: i64, y: i64 }
+///
+/// impl ToSql for Point {
+///     fn bind_sql(&self, stmt: &mut Statement, position: i32) -> Result<()> {
+///         format!("{},{}", self.x, self.y).bind_param(stmt, position)
+///     }
+/// }
+///
+/// fn insert(db: &Connection, p: &Point) -> Result<i64> {
+///     db.insert("INSERT INTO points VALUES (?)", [p])
+/// }
+/// ```
+///
+/// Binding a type by pointer, so that it can be retrieved by an application-defined
+/// function or virtual table using [ValueRef::get_ref]. This requires SQLite 3.20.0.
+///
+/// ```no_run
+/// use sqlite3_ext::{query::{Statement, ToParam, ToSql}, *};
+/// use std::rc::Rc;
+///
+/// struct Array(Rc<[Value]>);
+///
+/// impl ToSql for Array {
+///     fn bind_sql(&self, stmt: &mut Statement, position: i32) -> Result<()> {
+///         PassedRef::new(self.0.clone()).bind_param(stmt, position)
+///     }
+/// }
+/// ```
+pub trait ToSql {
+    /// Bind this value to the prepared Statement at the provided position.
+    fn bind_sql(&self, stmt: &mut Statement, position: i32) -> Result<()>;
+}
+
+macro_rules! to_sql_by_ref {
+    ($($ty:ty),*) => {
+        $(
+        impl ToSql for $ty {
+            fn bind_sql(&self, stmt: &mut Statement, pos: i32) -> Result<()> {
+                self.bind_param_ref(stmt, pos)
+            }
+        }
+        )*
+    };
+}
+
+to_sql_by_ref!(&str, String, &[u8], Vec<u8>, Value);
+
+/// Sets the parameter to the contained value or NULL.
+impl<T: ToSql> ToSql for Option<T> {
+    fn bind_sql(&self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        match self {
+            Some(x) => x.bind_sql(stmt, pos),
+            None => ().bind_param(stmt, pos),
+        }
+    }
+}
+
+#[sealed]
+impl<T: ToSql + ?Sized> ToParam for &T {
+    fn bind_param(self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        self.bind_sql(stmt, pos)
+    }
+
+    fn bind_param_ref(&self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        (**self).bind_sql(stmt, pos)
+    }
 }
@@ -1,6 +1,6 @@
 #![cfg(all(test, feature = "static"))]
 
-use crate::query::{Statement, ToParam};
+use crate::query::{PrepareFlags, Statement, StatementCache, StmtStatus, ToParam, ToSql};
 use crate::test_helpers::prelude::*;
 
 #[test]
@@ -211,3 +211,406 @@ fn reuse_statement() -> Result<()> {
     assert_eq!(ret, Value::Null);
     Ok(())
 }
+
+#[test]
+fn introspection() -> Result<()> {
+    let h = TestHelpers::new();
+    h.db.execute("CREATE TABLE tbl(a, b)", ())?;
+    h.db.execute("INSERT INTO tbl VALUES (1, 2), (3, 4)", ())?;
+    let mut stmt = h.db.prepare("SELECT a, b FROM tbl WHERE a > ?")?;
+    assert_eq!(stmt.data_count(), 0);
+    stmt.query([0])?;
+    assert!(stmt.next()?.is_some());
+    assert_eq!(stmt.data_count(), 2);
+    assert!(stmt.status(StmtStatus::FullscanStep, false)? >= 0);
+    // sqlite3_normalized_sql is not linked when statically linking to SQLite.
+    assert!(stmt.normalized_sql().is_err());
+    sqlite3_match_version! {
+        3_007_010 => assert!(stmt.busy()?),
+        _ => (),
+    }
+    sqlite3_match_version! {
+        3_007_004 => assert!(stmt.readonly()?),
+        _ => (),
+    }
+    sqlite3_match_version! {
+        3_014_000 => assert_eq!(stmt.expanded_sql()?, "SELECT a, b FROM tbl WHERE a > 0"),
+        _ => (),
+    }
+    sqlite3_match_version! {
+        3_028_000 => {
+            assert_eq!(stmt.explain_mode()?, crate::query::ExplainMode::Normal);
+            let stmt = h.db.prepare("EXPLAIN QUERY PLAN SELECT * FROM tbl")?;
+            assert_eq!(stmt.explain_mode()?, crate::query::ExplainMode::QueryPlan);
+            assert!(!h.db.prepare("INSERT INTO tbl VALUES (1, 2)")?.readonly()?);
+        }
+        _ => (),
+    }
+    while stmt.next()?.is_some() {}
+    assert_eq!(stmt.data_count(), 0);
+    assert!(stmt.status(StmtStatus::FullscanStep, true)? > 0);
+    assert_eq!(stmt.status(StmtStatus::FullscanStep, false)?, 0);
+    sqlite3_match_version! {
+        3_038_000 => assert!(stmt.status(StmtStatus::FilterHit, false).is_ok()),
+        _ => assert_eq!(
+            stmt.status(StmtStatus::FilterHit, false),
+            Err(Error::VersionNotSatisfied(3_038_000))
+        ),
+    }
+    Ok(())
+}
+
+#[test]
+fn statement_cache() -> Result<()> {
+    let h = TestHelpers::new();
+    h.db.execute("CREATE TABLE tbl(a)", ())?;
+    let mut cache = StatementCache::new(&h.db, 2);
+    for i in 0..3 {
+        cache.prepare("INSERT INTO tbl VALUES (?)")?.execute([i])?;
+    }
+    assert_eq!(cache.len(), 1);
+    {
+        let mut a = cache.prepare("SELECT COUNT(*) FROM tbl")?;
+        let mut b = cache.prepare("SELECT COUNT(*) FROM tbl")?;
+        assert_eq!(a.query_row((), |r| Ok(r[0].get_i64()))?, 3);
+        assert_eq!(b.query_row((), |r| Ok(r[0].get_i64()))?, 3);
+        assert_eq!(cache.len(), 1);
+    }
+    // The cache evicts the least recently used statement.
+    assert_eq!(cache.len(), 2);
+    cache.prepare("SELECT 1")?.discard();
+    assert_eq!(cache.len(), 2);
+    cache.prepare("SELECT 2")?;
+    assert_eq!(cache.len(), 2);
+    cache.set_capacity(1);
+    assert_eq!(cache.len(), 1);
+    cache.flush();
+    assert!(cache.is_empty());
+    Ok(())
+}
+
+#[test]
+fn statement_cache_schema_change() -> Result<()> {
+    let h = TestHelpers::new();
+    h.db.execute("CREATE TABLE tbl(a)", ())?;
+    h.db.execute("INSERT INTO tbl VALUES (1)", ())?;
+    let cache = StatementCache::new(&h.db, 4);
+    let sql = "SELECT * FROM tbl";
+    assert_eq!(cache.prepare(sql)?.query_row((), |r| Ok(r.len()))?, 1);
+    cache.prepare("SELECT 1")?;
+    assert_eq!(cache.len(), 2);
+    h.db.execute("ALTER TABLE tbl ADD COLUMN b DEFAULT 2", ())?;
+    let ret = cache
+        .prepare(sql)?
+        .query_row((), |r| Ok((r.len(), r[1].get_i64())))?;
+    assert_eq!(ret, (2, 2));
+    if cfg!(modern_sqlite) {
+        assert!(cache.is_empty());
+    }
+    Ok(())
+}
+
+#[test]
+fn prepare_with_flags() -> Result<()> {
+    let h = TestHelpers::new();
+    h.db.execute("CREATE TABLE tbl(a)", ())?;
+    h.db.prepare_with_flags("SELECT * FROM tbl", PrepareFlags::empty())?;
+    let ret =
+        h.db.prepare_with_flags("SELECT * FROM tbl", PrepareFlags::PERSISTENT);
+    sqlite3_match_version! {
+        3_020_000 => assert!(ret.is_ok()),
+        _ => assert_eq!(ret.unwrap_err(), Error::VersionNotSatisfied(3_020_000)),
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg(modern_sqlite)]
+fn prepare_no_vtab() -> Result<()> {
+    let h = TestHelpers::new();
+    h.db.prepare_with_flags("SELECT * FROM sqlite_schema", PrepareFlags::NO_VTAB)?;
+    let err =
+        h.db.prepare_with_flags("SELECT * FROM pragma_table_list", PrepareFlags::NO_VTAB)
+            .unwrap_err();
+    assert!(matches!(err, Error::Sqlite(ffi::SQLITE_ERROR, _)));
+    Ok(())
+}
+
+#[test]
+fn statements() -> Result<()> {
+    let h = TestHelpers::new();
+    let sql =
+        "CREATE TABLE tbl(a); -- comment\n;; INSERT INTO tbl VALUES (1); SELECT a FROM tbl;  ";
+    let mut statements = h.db.statements(sql);
+    let mut columns = vec![];
+    while let Some(mut stmt) = statements.next()? {
+        columns.push(stmt.column_count());
+        while stmt.next()?.is_some() {}
+    }
+    assert_eq!(columns, vec![0, 0, 1]);
+    assert!(h.db.statements("").next()?.is_none());
+    assert!(h.db.statements("SELECT 1; invalid").nth(1).is_err());
+    Ok(())
+}
+
+#[test]
+fn execute_batch() -> Result<()> {
+    let h = TestHelpers::new();
+    h.db.execute_batch(
+        "CREATE TABLE tbl(a); INSERT INTO tbl VALUES (1), (2); SELECT * FROM tbl; \
+         INSERT INTO tbl SELECT a + 2 FROM tbl;",
+    )?;
+    let count =
+        h.db.query_row("SELECT COUNT(*) FROM tbl", (), |r| Ok(r[0].get_i64()))?;
+    assert_eq!(count, 4);
+    h.db.execute_batch("")?;
+    assert!(h
+        .db
+        .execute_batch("INSERT INTO tbl VALUES (5); INSERT INTO missing VALUES (6)")
+        .is_err());
+    let count =
+        h.db.query_row("SELECT COUNT(*) FROM tbl", (), |r| Ok(r[0].get_i64()))?;
+    assert_eq!(count, 5);
+    Ok(())
+}
+
+#[test]
+fn column_index() -> Result<()> {
+    let h = TestHelpers::new();
+    h.db.execute("CREATE TABLE tbl(a, b)", ())?;
+    h.db.execute("INSERT INTO tbl VALUES (1, 2)", ())?;
+    let mut stmt = h.db.prepare("SELECT *, 3 AS a FROM tbl")?;
+    assert_eq!(stmt.column_index("a"), Some(0));
+    assert_eq!(stmt.column_index("b"), Some(1));
+    assert_eq!(stmt.column_index("c"), None);
+    let row = stmt.next()?.unwrap();
+    assert_eq!(row.get_by_name("b")?.get_i64(), 2);
+    assert!(row.get_by_name("c").is_err());
+    stmt.query(())?;
+    // The statement is recompiled after the schema change, and the new column is found.
+    h.db.execute("ALTER TABLE tbl ADD COLUMN c", ())?;
+    h.db.execute("UPDATE tbl SET c = 4", ())?;
+    let row = stmt.next()?.unwrap();
+    assert_eq!(row.get_by_name("c")?.get_i64(), 4);
+    assert_eq!(row.get_by_name("a")?.get_i64(), 1);
+    Ok(())
+}
+
+#[test]
+#[cfg(modern_sqlite)]
+fn column_index_renamed() -> Result<()> {
+    let h = TestHelpers::new();
+    h.db.execute("CREATE TABLE tbl(a)", ())?;
+    h.db.execute("INSERT INTO tbl VALUES (1)", ())?;
+    let mut stmt = h.db.prepare("SELECT * FROM tbl")?;
+    h.db.execute("ALTER TABLE tbl RENAME COLUMN a TO b", ())?;
+    assert_eq!(
+        stmt.query(())?.next()?.unwrap().get_by_name("b")?.get_i64(),
+        1
+    );
+    // Resetting the counter must not cause the old names to be reused.
+    stmt.status(StmtStatus::Reprepare, true)?;
+    h.db.execute("ALTER TABLE tbl RENAME COLUMN b TO c", ())?;
+    assert_eq!(
+        stmt.query(())?.next()?.unwrap().get_by_name("c")?.get_i64(),
+        1
+    );
+    assert_eq!(stmt.column_index("b"), None);
+    Ok(())
+}
+
+#[test]
+fn rows_owned() -> Result<()> {
+    let h = TestHelpers::new();
+    let mut stmt = h.db.query("VALUES (1, 'a'), (2, NULL)", ())?;
+    let rows: Vec<Vec<Value>> = stmt.rows_owned().collect::<Result<_>>()?;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Integer(1), Value::Text("a".to_owned())],
+            vec![Value::Integer(2), Value::Null],
+        ]
+    );
+    assert!(stmt.rows_owned().next().is_none());
+    stmt.query(())?;
+    assert_eq!(stmt.collect_values()?.len(), 2);
+
+    let mut stmt =
+        h.db.query("SELECT json_extract('x', '$') FROM (VALUES (1))", ())?;
+    let mut rows = stmt.rows_owned();
+    assert!(rows.next().unwrap().is_err());
+    assert!(rows.next().is_none());
+    Ok(())
+}
+
+#[test]
+fn iterator_adapters() -> Result<()> {
+    let h = TestHelpers::new();
+    let mut stmt = h.db.prepare("VALUES (1), (2), (3), (4)")?;
+    let mut seen = vec![];
+    stmt.query(())?.for_each(|r| {
+        seen.push(r[0].get_i64());
+        Ok(())
+    })?;
+    assert_eq!(seen, vec![1, 2, 3, 4]);
+    let evens: Vec<i64> = stmt
+        .query(())?
+        .filter_map(|r| {
+            let x = r[0].get_i64();
+            Ok(if x % 2 == 0 { Some(x) } else { None })
+        })
+        .collect()?;
+    assert_eq!(evens, vec![2, 4]);
+    let sum = stmt
+        .query(())?
+        .try_fold(0, |acc, r| Ok(acc + r[0].get_i64()))?;
+    assert_eq!(sum, 10);
+    // The adapters are ordinary FallibleIterators.
+    let total: i64 = stmt
+        .query(())?
+        .map(|r| Ok(r[0].get_i64()))
+        .filter(|x| Ok(*x > 1))
+        .fold(0, |acc, x| Ok(acc + x))?;
+    assert_eq!(total, 9);
+    assert!(stmt
+        .query(())?
+        .try_fold(0, |_, _| Err(SQLITE_MISMATCH))
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn named_params_collections() -> Result<()> {
+    use std::collections::{BTreeMap, HashMap};
+
+    let h = TestHelpers::new();
+    let sql = "SELECT :a, @b, $c";
+    let mut map = HashMap::new();
+    map.insert("a".to_owned(), Value::Integer(1));
+    map.insert("@b".to_owned(), Value::Text("two".to_owned()));
+    map.insert("$c".to_owned(), Value::Null);
+    let ret = h.db.query_row(sql, map, |r| {
+        Ok((r[0].get_i64(), r[1].get_str()?.to_owned(), r[2].is_null()))
+    })?;
+    assert_eq!(ret, (1, "two".to_owned(), true));
+
+    let mut map = BTreeMap::new();
+    map.insert("a", 1);
+    map.insert("b", 2);
+    map.insert("c", 3);
+    let ret = h.db.query_row(sql, map, |r| Ok(r[2].get_i64()))?;
+    assert_eq!(ret, 3);
+
+    let params: &[(&str, &dyn ToParam)] = &[("a", &1), ("b", &"two"), ("c", &Some(3.5))];
+    let ret = h.db.query_row(sql, params, |r| {
+        Ok((r[0].get_i64(), r[1].get_str()?.to_owned(), r[2].get_f64()))
+    })?;
+    assert_eq!(ret, (1, "two".to_owned(), 3.5));
+
+    let params: &[(&str, &dyn ToParam)] = &[("a", &("b", 1))];
+    match h.db.query_row(sql, params, |_| Ok(())) {
+        Err(Error::Sqlite(ffi::SQLITE_MISUSE, Some(msg))) => {
+            assert_eq!(msg, "named parameters cannot be bound by reference")
+        }
+        r => panic!("unexpected result {:?}", r),
+    }
+
+    let mut map = BTreeMap::new();
+    map.insert("d", 4);
+    match h.db.query_row(sql, map, |_| Ok(())) {
+        Err(Error::Sqlite(_, Some(msg))) => assert_eq!(msg, "no such parameter: d"),
+        r => panic!("unexpected result {:?}", r),
+    }
+    let stmt = h.db.prepare(sql)?;
+    assert_eq!(stmt.find_parameter("@b")?.get(), 2);
+    assert!(stmt.find_parameter(":c").is_err());
+    Ok(())
+}
+
+#[test]
+fn owned_params() -> Result<()> {
+    let h = TestHelpers::new();
+    let ret: Vec<Value> =
+        h.db.prepare("VALUES (?), (?), (?), (?), (?)")?
+            .query(params!(
+                -7i32,
+                u32::MAX,
+                "owned string".to_owned(),
+                vec![1u8, 2, 3],
+                Some("some".to_owned()),
+            ))?
+            .map(|r| r[0].to_owned())
+            .collect()?;
+    assert_eq!(
+        ret,
+        vec![
+            Value::Integer(-7),
+            Value::Integer(u32::MAX as i64),
+            Value::Text("owned string".to_owned()),
+            Value::Blob(Blob::from([1, 2, 3])),
+            Value::Text("some".to_owned()),
+        ]
+    );
+    let ret = h.db.query_row("SELECT ?", ["owned".to_owned()], |r| {
+        Ok(r[0].get_str()?.to_owned())
+    })?;
+    assert_eq!(ret, "owned");
+    Ok(())
+}
+
+#[test]
+fn to_sql() -> Result<()> {
+    struct Point {
+        x: i64,
+        y: i64,
+    }
+
+    impl ToSql for Point {
+        fn bind_sql(&self, stmt: &mut Statement, position: i32) -> Result<()> {
+            format!("{},{}", self.x, self.y).bind_param(stmt, position)
+        }
+    }
+
+    let h = TestHelpers::new();
+    let p = Point { x: 1, y: 2 };
+    let ret =
+        h.db.query_row("SELECT ?", [&p], |r| Ok(r[0].get_str()?.to_owned()))?;
+    assert_eq!(ret, "1,2");
+    let params: &[(&str, &dyn ToSql)] = &[("p", &p), ("n", &3), ("s", &"x")];
+    let ret =
+        h.db.query_row("SELECT :p || ',' || :n || :s", params, |r| {
+            Ok(r[0].get_str()?.to_owned())
+        })?;
+    assert_eq!(ret, "1,2,3x");
+    Ok(())
+}
+
+#[test]
+#[cfg(modern_sqlite)]
+fn to_sql_pointer() -> Result<()> {
+    use std::rc::Rc;
+
+    struct Array(Rc<[i64]>);
+
+    impl ToSql for Array {
+        fn bind_sql(&self, stmt: &mut Statement, position: i32) -> Result<()> {
+            PassedRef::new(self.0.clone()).bind_param(stmt, position)
+        }
+    }
+
+    let h = TestHelpers::new();
+    h.db.create_scalar_function(
+        "array_sum",
+        &FunctionOptions::default().set_n_args(1),
+        |c, args| {
+            let arr = args[0].get_ref::<Rc<[i64]>>().unwrap();
+            c.set_result(arr.iter().sum::<i64>())
+        },
+    )?;
+    let arr = Array(Rc::from(vec![1, 2, 3]));
+    let ret =
+        h.db.query_row("SELECT array_sum(?)", [&arr], |r| Ok(r[0].get_i64()))?;
+    assert_eq!(ret, 6);
+    assert_eq!(Rc::strong_count(&arr.0), 1);
+    Ok(())
+}
@@ -0,0 +1,220 @@
+//! Rust implementation of the rarray table-valued function.
+//!
+//! This module is a port of the carray extension distributed with SQLite, which allows an
+//! array of values to be passed into a query as a single parameter. Register the function
+//! on a connection using [register], then bind an [RArray] to the function's argument.
+//!
+//! ```no_run
+//! use sqlite3_ext::{rarray::*, *};
+//!
+//! fn find_users(db: &Connection, ids: &[i64]) -> Result<Vec<String>> {
+//!     register(db)?;
+//!     let ids: RArray = ids.iter().map(|&id| Value::from(id)).collect();
+//!     db.prepare("SELECT name FROM users WHERE id IN rarray(?)")?
+//!         .query([ids])?
+//!         .map(|r| Ok(r[0].get_str()?.to_owned()))
+//!         .collect()
+//! }
+//! ```
+//!
+//! The rarray function requires SQLite 3.20.0.
+//!
+//! For more information, consult [the SQLite documentation](https://sqlite.org/carray.html).
+
+use crate::{
+    query::{self, Statement, ToParam, ToSql},
+    sqlite3_require_version,
+    types::*,
+    value::*,
+    vtab::*,
+    Connection, RiskLevel,
+};
+use sealed::sealed;
+use std::rc::Rc;
+
+const COLUMN_POINTER: i32 = 1;
+
+/// An array of values which can be bound to the argument of the rarray function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RArray(pub Rc<[Value]>);
+
+impl From<Vec<Value>> for RArray {
+    fn from(values: Vec<Value>) -> Self {
+        RArray(values.into())
+    }
+}
+
+impl From<Rc<[Value]>> for RArray {
+    fn from(values: Rc<[Value]>) -> Self {
+        RArray(values)
+    }
+}
+
+impl FromIterator<Value> for RArray {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        RArray(iter.into_iter().collect())
+    }
+}
+
+/// Binds the array as a pointer which can be read by the rarray function. Requires SQLite
+/// 3.20.0.
+#[sealed]
+impl query::ToParam for RArray {
+    fn bind_param(self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        PassedRef::new(self.0).bind_param(stmt, pos)
+    }
+
+    fn bind_param_ref(&self, stmt: &mut Statement, pos: i32) -> Result<()> {
+        self.clone().bind_param(stmt, pos)
+    }
+}
+
+/// Allows an RArray to be bound by reference, so that it can be reused.
+impl ToSql for RArray {
+    fn bind_sql(&self, stmt: &mut Statement, position: i32) -> Result<()> {
+        self.clone().bind_param(stmt, position)
+    }
+}
+
+/// Register the rarray function on the connection.
+///
+/// Requires SQLite 3.20.0.
+pub fn register(db: &Connection) -> Result<()> {
+    sqlite3_require_version!(3_020_000)?;
+    db.create_module("rarray", RArrayVTab::module(), ())
+}
+
+/// The virtual table implementing the rarray function. Use [register] to add it to a
+/// connection.
+pub struct RArrayVTab;
+
+impl RArrayVTab {
+    /// Return the [Module] for this virtual table.
+    pub fn module() -> EponymousModule<'static, Self> {
+        EponymousModule::new()
+    }
+}
+
+impl VTab<'_> for RArrayVTab {
+    type Aux = ();
+    type Cursor = RArrayCursor;
+
+    fn connect(db: &VTabConnection, _aux: &Self::Aux, _args: &[&str]) -> Result<(String, Self)> {
+        db.set_risk_level(RiskLevel::Innocuous);
+        Ok((
+            "CREATE TABLE x ( value, pointer HIDDEN )".to_owned(),
+            RArrayVTab,
+        ))
+    }
+
+    fn best_index(&self, index_info: &mut IndexInfo) -> Result<()> {
+        let mut has_ptr = false;
+        for mut constraint in index_info.constraints() {
+            if !constraint.usable() {
+                continue;
+            }
+            if constraint.op() != ConstraintOp::Eq {
+                continue;
+            }
+            if constraint.column() == COLUMN_POINTER {
+                has_ptr = true;
+                constraint.set_argv_index(Some(0));
+                constraint.set_omit(true);
+            }
+        }
+        if has_ptr {
+            index_info.set_estimated_cost(1f64);
+            index_info.set_estimated_rows(100);
+        } else {
+            index_info.set_estimated_cost(2147483647f64);
+            index_info.set_estimated_rows(2147483647);
+        }
+        Ok(())
+    }
+
+    fn open(&self) -> Result<Self::Cursor> {
+        Ok(RArrayCursor::default())
+    }
+}
+
+/// Cursor for [RArrayVTab].
+#[derive(Default, Debug)]
+pub struct RArrayCursor {
+    rowid: i64,
+    array: Option<Rc<[Value]>>,
+}
+
+impl VTabCursor for RArrayCursor {
+    fn filter(&mut self, _: i32, _: Option<&str>, args: &mut [&mut ValueRef]) -> Result<()> {
+        self.rowid = 0;
+        self.array = args
+            .first()
+            .and_then(|a| a.get_ref::<Rc<[Value]>>())
+            .cloned();
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<()> {
+        self.rowid += 1;
+        Ok(())
+    }
+
+    fn eof(&mut self) -> bool {
+        self.rowid as usize >= self.array.as_ref().map(|a| a.len()).unwrap_or(0)
+    }
+
+    fn column(&mut self, idx: usize, c: &ColumnContext) -> Result<()> {
+        match idx as _ {
+            COLUMN_POINTER => Ok(()),
+            _ => c.set_result(self.array.as_ref().map(|a| a[self.rowid as usize].clone())),
+        }
+    }
+
+    fn rowid(&mut self) -> Result<i64> {
+        Ok(self.rowid)
+    }
+}
+
+#[cfg(all(modern_sqlite, test, feature = "static"))]
+mod test {
+    use super::*;
+    use crate::test_helpers::prelude::*;
+
+    #[test]
+    fn rarray() -> Result<()> {
+        let h = TestHelpers::new();
+        register(&h.db)?;
+        let array: RArray = vec![1, 2, 3, 4].into_iter().map(Value::from).collect();
+        let results: Vec<i64> =
+            h.db.prepare("SELECT * FROM rarray(?)")?
+                .query([&array])?
+                .map(|row| Ok(row[0].get_i64()))
+                .collect()?;
+        assert_eq!(results, vec![1, 2, 3, 4]);
+        let count =
+            h.db.query_row("SELECT COUNT(*) FROM rarray(NULL)", (), |r| {
+                Ok(r[0].get_i64())
+            })?;
+        assert_eq!(count, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn rarray_in() -> Result<()> {
+        let h = TestHelpers::new();
+        register(&h.db)?;
+        h.db.execute_batch(
+            "CREATE TABLE t(id, name); \
+             INSERT INTO t VALUES (1, 'a'), (2, 'b'), (3, 'c'), (4, 'd');",
+        )?;
+        let ids = RArray::from(vec![Value::from(2), Value::from(4), Value::from(5)]);
+        let names: Vec<String> =
+            h.db.prepare("SELECT name FROM t WHERE id IN rarray(?) ORDER BY id")?
+                .query([ids.clone()])?
+                .map(|r| Ok(r[0].get_str()?.to_owned()))
+                .collect()?;
+        assert_eq!(names, vec!["b", "d"]);
+        assert_eq!(Rc::strong_count(&ids.0), 1);
+        Ok(())
+    }
+}
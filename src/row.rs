@@ -0,0 +1,50 @@
+use crate::{query::QueryResult, types::*, value::*};
+
+/// A type which can be constructed from a row of SQLite values.
+///
+/// The values given to [from_row](FromRow::from_row) are provided in the order that the
+/// columns were declared. For example, a virtual table implementing
+/// [UpdateVTab](crate::vtab::UpdateVTab) through
+/// [CollectionVTab](crate::vtab::CollectionVTab) will receive the columns in the order they
+/// appear in the table's declaration. Query results are converted using
+/// [from_query_result](FromRow::from_query_result), which by default also maps the columns
+/// by position.
+///
+/// This trait can be derived using [derive(FromRow)](macro@crate::FromRow), which maps
+/// query results by column name.
+///
+/// # Examples
+///
+/// ```no_run
+/// use sqlite3_ext::*;
+///
+/// struct Person {
+///     name: String,
+///     age: i64,
+/// }
+///
+/// impl FromRow for Person {
+///     fn from_row(values: &mut [&mut ValueRef]) -> Result<Self> {
+///         Ok(Person {
+///             name: values[0].get_str()?.to_owned(),
+///             age: values[1].get_i64(),
+///         })
+///     }
+/// }
+/// ```
+pub trait FromRow: Sized {
+    /// Construct a new instance from the provided values.
+    fn from_row(values: &mut [&mut ValueRef]) -> Result<Self>;
+
+    /// Construct a new instance from a row returned by a query. This is used by
+    /// [Statement::query_map](crate::query::Statement::query_map) and
+    /// [Connection::query_one](crate::Connection::query_one).
+    ///
+    /// The default implementation passes the columns to [from_row](FromRow::from_row) in
+    /// the order they appear in the result.
+    fn from_query_result(row: &mut QueryResult<'_>) -> Result<Self> {
+        let mut values: Vec<&mut ValueRef> =
+            row.columns_mut().iter_mut().map(|c| c.as_mut()).collect();
+        Self::from_row(&mut values)
+    }
+}
@@ -63,6 +63,30 @@ impl TestHelpers {
     }
 }
 
+/// A path for a database file in the temporary directory. The database and its journal
+/// files are removed when this is dropped.
+pub struct TempDatabase {
+    pub path: std::path::PathBuf,
+}
+
+impl TempDatabase {
+    pub fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("sqlite3_ext_{}_{}.db", name, std::process::id()));
+        TempDatabase { path }
+    }
+}
+
+impl Drop for TempDatabase {
+    fn drop(&mut self) {
+        for suffix in ["", "-journal", "-wal", "-shm"] {
+            let mut path = self.path.clone().into_os_string();
+            path.push(suffix);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
 #[test]
 fn with_value() {
     let h = TestHelpers::new();
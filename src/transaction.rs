@@ -1,6 +1,7 @@
-use super::{types::*, Connection};
+use super::{ffi, sqlite3_require_version, types::*, Connection};
 
 /// The type of transaction to create.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum TransactionType {
     /// The transaction does not actually start until the database is first accessed. If the first
     /// statement in the transaction is a SELECT, then a read transaction is started. Subsequent
@@ -19,10 +20,45 @@ pub enum TransactionType {
     Exclusive,
 }
 
+/// The transaction state of a database. See [Connection::txn_state].
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum TxnState {
+    /// No transaction is currently pending.
+    None,
+    /// The database is currently in a read transaction. Content has been read from the
+    /// database file but nothing in the database file has changed.
+    Read,
+    /// The database is currently in a write transaction. Content has been written to the
+    /// database file, or a write transaction was started with BEGIN IMMEDIATE or BEGIN
+    /// EXCLUSIVE.
+    Write,
+}
+
+/// How to retry beginning a transaction which fails with SQLITE_BUSY. See
+/// [Connection::transaction_with_retry].
+///
+/// BEGIN IMMEDIATE and BEGIN EXCLUSIVE fail with SQLITE_BUSY when another connection holds
+/// a conflicting lock on the database. Note that a [busy timeout](https://www.sqlite.org/c3ref/busy_timeout.html)
+/// configured on the connection is applied before SQLite reports SQLITE_BUSY.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct BusyRetry {
+    /// The number of times to retry, after the initial attempt fails.
+    pub attempts: u32,
+    /// The amount of time to sleep before each retry.
+    pub delay: std::time::Duration,
+}
+
+impl BusyRetry {
+    /// Retry the given number of times, sleeping for delay between each attempt.
+    pub const fn new(attempts: u32, delay: std::time::Duration) -> Self {
+        BusyRetry { attempts, delay }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 enum TransactionState {
     ActiveTransaction,
-    ActiveSavepoint,
+    ActiveSavepoint(String),
     Inactive,
 }
 
@@ -46,7 +82,17 @@ pub struct Transaction<'db> {
 
 impl Connection {
     /// Starts a new transaction with the specified behavior.
+    ///
+    /// Transactions cannot be nested. If a transaction is already open on this connection,
+    /// this method fails with SQLITE_MISUSE. Use [Transaction::savepoint] to create a
+    /// nested transaction.
     pub fn transaction(&self, tt: TransactionType) -> Result<Transaction<'_>> {
+        if !self.is_autocommit() {
+            return Err(Error::Sqlite(
+                ffi::SQLITE_MISUSE,
+                Some("cannot start a transaction within a transaction".to_owned()),
+            ));
+        }
         let mut txn = Transaction {
             db: self,
             state: TransactionState::Inactive,
@@ -54,6 +100,107 @@ impl Connection {
         txn.start(tt)?;
         Ok(txn)
     }
+
+    /// Starts a new transaction with the specified behavior, retrying according to the
+    /// given policy if the database is busy.
+    ///
+    /// Only [Immediate](TransactionType::Immediate) and
+    /// [Exclusive](TransactionType::Exclusive) transactions acquire a lock when they begin,
+    /// so this method behaves identically to [transaction](Connection::transaction) for
+    /// [Deferred](TransactionType::Deferred) transactions.
+    pub fn transaction_with_retry(
+        &self,
+        tt: TransactionType,
+        retry: BusyRetry,
+    ) -> Result<Transaction<'_>> {
+        let mut attempts = 0;
+        loop {
+            match self.transaction(tt) {
+                Err(Error::Sqlite(rc, _))
+                    if rc & 0xff == ffi::SQLITE_BUSY && attempts < retry.attempts =>
+                {
+                    attempts += 1;
+                    std::thread::sleep(retry.delay);
+                }
+                ret => return ret,
+            }
+        }
+    }
+
+    /// Run the function inside of a new transaction. If the function returns Ok, the
+    /// transaction is committed. If the function returns Err or panics, the transaction is
+    /// rolled back.
+    ///
+    /// When the function returns Err, its error is always returned, even if the rollback
+    /// fails. If SQLite has already rolled back the transaction, for example after an
+    /// `INSERT OR ROLLBACK` constraint violation, no rollback is attempted.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use sqlite3_ext::*;
+    ///
+    /// fn transfer(db: &Connection, amount: i64) -> Result<()> {
+    ///     db.with_transaction(TransactionType::Immediate, |txn| {
+    ///         txn.execute("UPDATE accounts SET balance = balance - ? WHERE id = 1", [amount])?;
+    ///         txn.execute("UPDATE accounts SET balance = balance + ? WHERE id = 2", [amount])?;
+    ///         Ok(())
+    ///     })
+    /// }
+    /// ```
+    pub fn with_transaction<T, F: FnOnce(&mut Transaction<'_>) -> Result<T>>(
+        &self,
+        tt: TransactionType,
+        f: F,
+    ) -> Result<T> {
+        let mut txn = self.transaction(tt)?;
+        match f(&mut txn) {
+            Ok(ret) => {
+                txn.commit()?;
+                Ok(ret)
+            }
+            Err(e) => {
+                if self.is_autocommit() {
+                    txn.state = TransactionState::Inactive;
+                } else {
+                    let _ = txn.rollback();
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns true if the connection is in autocommit mode, which is the case when no
+    /// transaction is open.
+    pub fn is_autocommit(&self) -> bool {
+        unsafe { ffi::sqlite3_get_autocommit(self.as_mut_ptr()) != 0 }
+    }
+
+    /// Returns the transaction state of the given database, or the highest transaction
+    /// state of all attached databases if schema is None.
+    ///
+    /// Requires SQLite 3.34.0.
+    pub fn txn_state(&self, schema: Option<&str>) -> Result<TxnState> {
+        let _ = schema;
+        sqlite3_require_version!(3_034_000, {
+            let schema_c = schema.map(std::ffi::CString::new).transpose()?;
+            let rc = unsafe {
+                ffi::sqlite3_txn_state(
+                    self.as_mut_ptr(),
+                    schema_c.as_ref().map_or(std::ptr::null(), |s| s.as_ptr()),
+                )
+            };
+            match rc {
+                ffi::SQLITE_TXN_NONE => Ok(TxnState::None),
+                ffi::SQLITE_TXN_READ => Ok(TxnState::Read),
+                ffi::SQLITE_TXN_WRITE => Ok(TxnState::Write),
+                _ => Err(Error::Sqlite(
+                    ffi::SQLITE_ERROR,
+                    Some(format!("unknown database {}", schema.unwrap_or_default())),
+                )),
+            }
+        })
+    }
 }
 
 impl<'db> Transaction<'db> {
@@ -74,9 +221,11 @@ impl<'db> Transaction<'db> {
     }
 
     fn commit_mut(&mut self) -> Result<()> {
-        let ret = match self.state {
+        let ret = match &self.state {
             TransactionState::ActiveTransaction => self.execute("COMMIT", ()),
-            TransactionState::ActiveSavepoint => self.execute("RELEASE SAVEPOINT a", ()),
+            TransactionState::ActiveSavepoint(name) => {
+                self.execute(&format!("RELEASE SAVEPOINT {}", name), ())
+            }
             TransactionState::Inactive => panic!("lifetime error"),
         };
         self.state = TransactionState::Inactive;
@@ -89,23 +238,53 @@ impl<'db> Transaction<'db> {
     }
 
     fn rollback_mut(&mut self) -> Result<()> {
-        let ret = match self.state {
+        let ret = match &self.state {
             TransactionState::ActiveTransaction => self.execute("ROLLBACK", ()),
-            TransactionState::ActiveSavepoint => self.execute("ROLLBACK TO a", ()),
+            TransactionState::ActiveSavepoint(name) => self
+                .execute(&format!("ROLLBACK TO {}", name), ())
+                .and_then(|_| self.execute(&format!("RELEASE SAVEPOINT {}", name), ())),
             TransactionState::Inactive => panic!("lifetime error"),
         };
         self.state = TransactionState::Inactive;
         ret.map(|_| ())
     }
 
+    /// Roll back all changes made since this savepoint was created, without ending the
+    /// savepoint. Further statements can be executed and the savepoint can be committed or
+    /// rolled back as usual.
+    ///
+    /// This method fails with SQLITE_MISUSE if this Transaction is not a savepoint.
+    pub fn rollback_to(&mut self) -> Result<()> {
+        match &self.state {
+            TransactionState::ActiveSavepoint(name) => self
+                .execute(&format!("ROLLBACK TO {}", name), ())
+                .map(|_| ()),
+            _ => Err(Error::Sqlite(
+                ffi::SQLITE_MISUSE,
+                Some("rollback_to requires a savepoint".to_owned()),
+            )),
+        }
+    }
+
     /// Create a savepoint for the current transaction. This functions identically to a
     /// transaction, but committing or rolling back will only affect statements since the savepoint
     /// was created.
     pub fn savepoint(&mut self) -> Result<Transaction<'_>> {
-        self.execute("SAVEPOINT a", ())?;
+        self.start_savepoint("\"a\"".to_owned())
+    }
+
+    /// Create a savepoint with the given name. This is identical to
+    /// [savepoint](Transaction::savepoint), except that the savepoint can also be referred
+    /// to by name in SQL statements, for example `ROLLBACK TO name`.
+    pub fn savepoint_named(&mut self, name: &str) -> Result<Transaction<'_>> {
+        self.start_savepoint(format!("\"{}\"", name.replace('"', "\"\"")))
+    }
+
+    fn start_savepoint(&mut self, name: String) -> Result<Transaction<'_>> {
+        self.execute(&format!("SAVEPOINT {}", name), ())?;
         let txn = Self {
             db: self.db,
-            state: TransactionState::ActiveSavepoint,
+            state: TransactionState::ActiveSavepoint(name),
         };
         Ok(txn)
     }
@@ -226,6 +405,158 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn nested() -> Result<()> {
+        let h = TestHelpers::new();
+        assert!(h.db.is_autocommit());
+        let txn = h.db.transaction(TransactionType::Deferred)?;
+        assert!(!h.db.is_autocommit());
+        match h.db.transaction(TransactionType::Deferred) {
+            Ok(_) => unreachable!(),
+            Err(e) => assert_eq!(
+                e,
+                Error::Sqlite(
+                    ffi::SQLITE_MISUSE,
+                    Some("cannot start a transaction within a transaction".to_owned())
+                )
+            ),
+        }
+        txn.commit()?;
+        assert!(h.db.is_autocommit());
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(modern_sqlite)]
+    fn txn_state() -> Result<()> {
+        let h = TestHelpers::new();
+        h.db.execute("CREATE TABLE tbl(col)", ())?;
+        assert_eq!(h.db.txn_state(None)?, TxnState::None);
+        let txn = h.db.transaction(TransactionType::Deferred)?;
+        assert_eq!(txn.txn_state(Some("main"))?, TxnState::None);
+        txn.query_row("SELECT COUNT(*) FROM tbl", (), |_| Ok(()))?;
+        assert_eq!(txn.txn_state(Some("main"))?, TxnState::Read);
+        txn.execute("INSERT INTO tbl VALUES (1)", ())?;
+        assert_eq!(txn.txn_state(None)?, TxnState::Write);
+        assert!(txn.txn_state(Some("nonexistent")).is_err());
+        txn.commit()?;
+        assert_eq!(h.db.txn_state(None)?, TxnState::None);
+        Ok(())
+    }
+
+    #[test]
+    fn savepoint_named() -> Result<()> {
+        let h = TestHelpers::new();
+        h.db.execute("CREATE TABLE tbl(col)", ())?;
+        let mut txn = h.db.transaction(TransactionType::Deferred)?;
+        txn.execute("INSERT INTO tbl VALUES (1)", ())?;
+        let mut sp = txn.savepoint_named("my \"savepoint\"")?;
+        sp.execute("INSERT INTO tbl VALUES (2)", ())?;
+        sp.rollback_to()?;
+        sp.execute("INSERT INTO tbl VALUES (3)", ())?;
+        sp.execute("ROLLBACK TO \"my \"\"savepoint\"\"\"", ())?;
+        sp.execute("INSERT INTO tbl VALUES (4)", ())?;
+        sp.commit()?;
+        txn.commit()?;
+        let vals: Vec<i64> =
+            h.db.query("SELECT col FROM tbl", ())?
+                .map(|r| Ok(r[0].get_i64()))
+                .collect()?;
+        assert_eq!(vals, vec![1, 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn savepoint_rollback_releases() -> Result<()> {
+        let h = TestHelpers::new();
+        let mut txn = h.db.transaction(TransactionType::Deferred)?;
+        txn.savepoint_named("sp")?.rollback()?;
+        assert!(txn.execute("RELEASE sp", ()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rollback_to_transaction() -> Result<()> {
+        let h = TestHelpers::new();
+        let mut txn = h.db.transaction(TransactionType::Deferred)?;
+        match txn.rollback_to() {
+            Err(Error::Sqlite(rc, _)) => assert_eq!(rc, ffi::SQLITE_MISUSE),
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn with_transaction() -> Result<()> {
+        let h = TestHelpers::new();
+        h.db.execute("CREATE TABLE tbl(col)", ())?;
+        let ret = h.db.with_transaction(TransactionType::Deferred, |txn| {
+            txn.execute("INSERT INTO tbl VALUES (1)", ())?;
+            Ok(10)
+        })?;
+        assert_eq!(ret, 10);
+        let ret: Result<()> = h.db.with_transaction(TransactionType::Deferred, |txn| {
+            txn.execute("INSERT INTO tbl VALUES (2)", ())?;
+            Err(Error::Module("failed".to_owned()))
+        });
+        assert_eq!(ret, Err(Error::Module("failed".to_owned())));
+        let ret = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            h.db.with_transaction::<(), _>(TransactionType::Deferred, |txn| {
+                txn.execute("INSERT INTO tbl VALUES (3)", ())?;
+                panic!("failed");
+            })
+        }));
+        assert!(ret.is_err());
+        assert!(h.db.is_autocommit());
+        let vals: Vec<i64> =
+            h.db.query("SELECT col FROM tbl", ())?
+                .map(|r| Ok(r[0].get_i64()))
+                .collect()?;
+        assert_eq!(vals, vec![1]);
+        Ok(())
+    }
+
+    #[test]
+    fn with_transaction_ended_by_sqlite() -> Result<()> {
+        let h = TestHelpers::new();
+        h.db.execute("CREATE TABLE tbl(col UNIQUE)", ())?;
+        h.db.execute("INSERT INTO tbl VALUES (1)", ())?;
+        // OR ROLLBACK causes SQLite to end the transaction itself.
+        let ret: Result<()> = h.db.with_transaction(TransactionType::Deferred, |txn| {
+            txn.execute("INSERT INTO tbl VALUES (2)", ())?;
+            txn.execute("INSERT OR ROLLBACK INTO tbl VALUES (1)", ())?;
+            Ok(())
+        });
+        match ret {
+            Err(Error::Sqlite(rc, _)) => assert_eq!(rc & 0xff, ffi::SQLITE_CONSTRAINT),
+            x => panic!("expected SQLITE_CONSTRAINT, got {:?}", x),
+        }
+        assert!(h.db.is_autocommit());
+        let vals: Vec<i64> =
+            h.db.query("SELECT col FROM tbl", ())?
+                .map(|r| Ok(r[0].get_i64()))
+                .collect()?;
+        assert_eq!(vals, vec![1]);
+        Ok(())
+    }
+
+    #[test]
+    fn busy_retry() -> Result<()> {
+        let temp = TempDatabase::new("busy_retry");
+        let db1 = Database::open(&temp.path)?;
+        let db2 = Database::open(&temp.path)?;
+        let txn = db1.transaction(TransactionType::Immediate)?;
+        let retry = BusyRetry::new(2, std::time::Duration::from_millis(1));
+        match db2.transaction_with_retry(TransactionType::Immediate, retry) {
+            Err(Error::Sqlite(rc, _)) => assert_eq!(rc & 0xff, ffi::SQLITE_BUSY),
+            _ => unreachable!(),
+        }
+        txn.commit()?;
+        db2.transaction_with_retry(TransactionType::Immediate, retry)?
+            .commit()?;
+        Ok(())
+    }
+
     #[test]
     fn commit_fail() -> Result<()> {
         let h = TestHelpers::new();
@@ -42,6 +42,9 @@ pub enum Error {
     /// The result was not necessary to produce because it is an unchanged column in an
     /// UPDATE operation. See [ValueRef::nochange](crate::ValueRef::nochange) for details.
     NoChange,
+    /// A statement was interrupted because its deadline passed. See
+    /// [Statement::with_deadline](crate::query::Statement::with_deadline).
+    DeadlineExceeded,
 }
 
 impl Error {
@@ -93,13 +96,17 @@ impl Error {
             | e @ Error::NulError(_)
             | e @ Error::VersionNotSatisfied(_)
             | e @ Error::Module(_)
-            | e @ Error::NoChange => {
+            | e @ Error::NoChange
+            | e @ Error::DeadlineExceeded => {
                 if !msg.is_null() {
                     if let Ok(s) = ffi::str_to_sqlite3(&format!("{}", e)) {
                         unsafe { *msg = s };
                     }
                 }
-                ffi::SQLITE_ERROR
+                match e {
+                    Error::DeadlineExceeded => ffi::SQLITE_INTERRUPT,
+                    _ => ffi::SQLITE_ERROR,
+                }
             }
         }
     }
@@ -143,6 +150,7 @@ impl std::fmt::Display for Error {
                 v % 1000
             ),
             Error::NoChange => write!(f, "invalid Error::NoChange"),
+            Error::DeadlineExceeded => write!(f, "statement deadline exceeded"),
         }
     }
 }
@@ -169,6 +177,7 @@ impl std::fmt::Debug for Error {
                 f.debug_tuple("VersionNotSatisfied").field(&v).finish()
             }
             Error::NoChange => f.debug_tuple("NoChange").finish(),
+            Error::DeadlineExceeded => f.debug_tuple("DeadlineExceeded").finish(),
         }
     }
 }
@@ -323,3 +323,44 @@ value_from!(f64 as (x) => Value::Float(x));
 value_from!(String as (x) => Value::Text(x));
 value_from!(Blob as (x) => Value::Blob(x));
 value_from!(() as (_x) => Value::Null);
+
+/// A type which can be converted from an SQLite value. This trait is used by
+/// [macro@FromRow](crate::FromRow) to convert each column to the type of the corresponding
+/// field.
+///
+/// Conversions follow the same rules as the methods of [FromValue]; for example, converting
+/// NULL to an i64 results in 0. Use `Option<T>` to distinguish NULL values.
+pub trait FromSql: Sized {
+    /// Convert the value.
+    fn from_sql(value: &mut ValueRef) -> Result<Self>;
+}
+
+macro_rules! from_sql {
+    ($ty:ty as ($x:ident) => $impl:expr) => {
+        impl FromSql for $ty {
+            fn from_sql($x: &mut ValueRef) -> Result<$ty> {
+                $impl
+            }
+        }
+    };
+}
+
+from_sql!(bool as (x) => Ok(x.get_i64() != 0));
+from_sql!(i32 as (x) => Ok(x.get_i32()));
+from_sql!(u32 as (x) => Ok(x.get_i64() as u32));
+from_sql!(i64 as (x) => Ok(x.get_i64()));
+from_sql!(f64 as (x) => Ok(x.get_f64()));
+from_sql!(String as (x) => Ok(x.get_str()?.to_owned()));
+from_sql!(Vec<u8> as (x) => Ok(x.get_blob()?.to_vec()));
+from_sql!(Blob as (x) => Ok(Blob::from(x.get_blob()?)));
+from_sql!(Value as (x) => FromValue::to_owned(x));
+
+impl<T: FromSql> FromSql for Option<T> {
+    fn from_sql(value: &mut ValueRef) -> Result<Option<T>> {
+        if value.is_null() {
+            Ok(None)
+        } else {
+            T::from_sql(value).map(Some)
+        }
+    }
+}
@@ -0,0 +1,494 @@
+//! Virtual tables backed by Rust collections.
+
+use super::*;
+use crate::FromRow;
+use std::{cell::RefCell, collections::BTreeMap, ops::Bound, rc::Rc};
+
+/// A collection of rows which can be exposed through [CollectionVTab].
+///
+/// Each row in the collection is identified by a unique rowid. Implementations are
+/// provided for `Vec<T>`, which uses the index of each element as the rowid, and
+/// `BTreeMap<K, T>` for integer keys, which uses the key as the rowid.
+pub trait Collection {
+    /// The type of the rows stored in this collection.
+    type Row: ToRow;
+
+    /// Return the smallest rowid in the collection which satisfies the given lower bound.
+    fn first_rowid(&self, lower: Bound<i64>) -> Option<i64>;
+
+    /// Return the row with the given rowid.
+    fn get(&self, rowid: i64) -> Option<&Self::Row>;
+
+    /// Return the number of rows in the collection.
+    fn len(&self) -> usize;
+
+    /// Return true if the collection has no rows.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Insert a new row into the collection. If rowid is None, the collection must choose
+    /// a rowid for the new row. Returns the rowid of the inserted row.
+    fn insert(&mut self, rowid: Option<i64>, row: Self::Row) -> Result<i64>;
+
+    /// Replace the row with rowid old_rowid with a new row, located at new_rowid.
+    fn replace(&mut self, old_rowid: i64, new_rowid: i64, row: Self::Row) -> Result<()>;
+
+    /// Remove the row with the given rowid from the collection.
+    fn remove(&mut self, rowid: i64) -> Result<()>;
+}
+
+/// Uses the index of each element as the rowid.
+///
+/// Because removing an element from a Vec shifts the indexes of all following elements,
+/// only the last element can be removed, and rows cannot be moved to a different rowid.
+/// New rows can only be inserted at the end of the Vec.
+impl<T: ToRow> Collection for Vec<T> {
+    type Row = T;
+
+    fn first_rowid(&self, lower: Bound<i64>) -> Option<i64> {
+        let first = match lower {
+            Bound::Unbounded => 0,
+            Bound::Included(x) => x.max(0),
+            Bound::Excluded(x) => x.checked_add(1)?.max(0),
+        };
+        if (first as u64) < self.len() as u64 {
+            Some(first)
+        } else {
+            None
+        }
+    }
+
+    fn get(&self, rowid: i64) -> Option<&T> {
+        usize::try_from(rowid)
+            .ok()
+            .and_then(|i| <[T]>::get(self, i))
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn insert(&mut self, rowid: Option<i64>, row: T) -> Result<i64> {
+        let next = Vec::len(self) as i64;
+        match rowid {
+            Some(x) if x != next => Err(Error::Module(format!(
+                "rowid {} is not the end of the collection",
+                x
+            ))),
+            _ => {
+                self.push(row);
+                Ok(next)
+            }
+        }
+    }
+
+    fn replace(&mut self, old_rowid: i64, new_rowid: i64, row: T) -> Result<()> {
+        if old_rowid != new_rowid {
+            return Err(Error::Module("cannot change the rowid of a row".to_owned()));
+        }
+        match usize::try_from(old_rowid)
+            .ok()
+            .and_then(|i| self.get_mut(i))
+        {
+            Some(x) => {
+                *x = row;
+                Ok(())
+            }
+            None => Err(SQLITE_NOTFOUND),
+        }
+    }
+
+    fn remove(&mut self, rowid: i64) -> Result<()> {
+        if Vec::len(self) == 0 || rowid != Vec::len(self) as i64 - 1 {
+            return Err(Error::Module(
+                "only the last row can be removed from the collection".to_owned(),
+            ));
+        }
+        self.pop();
+        Ok(())
+    }
+}
+
+/// Uses the key of each element as the rowid.
+///
+/// When a row is inserted without a rowid, the new rowid will be one greater than the
+/// largest existing key, or 1 if the collection is empty.
+impl<K, T> Collection for BTreeMap<K, T>
+where
+    K: Ord + Copy + Into<i64> + TryFrom<i64>,
+    T: ToRow,
+{
+    type Row = T;
+
+    fn first_rowid(&self, lower: Bound<i64>) -> Option<i64> {
+        let lower = match lower {
+            Bound::Unbounded => Bound::Unbounded,
+            Bound::Included(x) | Bound::Excluded(x) => match K::try_from(x) {
+                Ok(k) => match lower {
+                    Bound::Included(_) => Bound::Included(k),
+                    _ => Bound::Excluded(k),
+                },
+                // The bound is outside of the range of K, so it is either below every key
+                // or above every key.
+                Err(_) => {
+                    let first: i64 = (*self.keys().next()?).into();
+                    if x < first {
+                        Bound::Unbounded
+                    } else {
+                        return None;
+                    }
+                }
+            },
+        };
+        self.range((lower, Bound::Unbounded))
+            .next()
+            .map(|(k, _)| (*k).into())
+    }
+
+    fn get(&self, rowid: i64) -> Option<&T> {
+        K::try_from(rowid)
+            .ok()
+            .and_then(|k| BTreeMap::get(self, &k))
+    }
+
+    fn len(&self) -> usize {
+        BTreeMap::len(self)
+    }
+
+    fn insert(&mut self, rowid: Option<i64>, row: T) -> Result<i64> {
+        let rowid = match rowid {
+            Some(x) => x,
+            None => match self.keys().next_back() {
+                Some(k) => Into::<i64>::into(*k)
+                    .checked_add(1)
+                    .ok_or(SQLITE_CONSTRAINT)?,
+                None => 1,
+            },
+        };
+        let key = K::try_from(rowid).map_err(|_| SQLITE_CONSTRAINT)?;
+        if self.contains_key(&key) {
+            return Err(SQLITE_CONSTRAINT);
+        }
+        BTreeMap::insert(self, key, row);
+        Ok(rowid)
+    }
+
+    fn replace(&mut self, old_rowid: i64, new_rowid: i64, row: T) -> Result<()> {
+        let old_key = K::try_from(old_rowid).map_err(|_| SQLITE_NOTFOUND)?;
+        let new_key = K::try_from(new_rowid).map_err(|_| SQLITE_CONSTRAINT)?;
+        if !self.contains_key(&old_key) {
+            return Err(SQLITE_NOTFOUND);
+        }
+        if old_key != new_key {
+            if self.contains_key(&new_key) {
+                return Err(SQLITE_CONSTRAINT);
+            }
+            BTreeMap::remove(self, &old_key);
+        }
+        BTreeMap::insert(self, new_key, row);
+        Ok(())
+    }
+
+    fn remove(&mut self, rowid: i64) -> Result<()> {
+        K::try_from(rowid)
+            .ok()
+            .and_then(|k| BTreeMap::remove(self, &k))
+            .map(|_| ())
+            .ok_or(SQLITE_NOTFOUND)
+    }
+}
+
+/// An eponymous virtual table which exposes a Rust [Collection].
+///
+/// The collection is shared between the virtual table and the rest of the program using
+/// an `Rc<RefCell<C>>`, which is passed as the aux data to [Connection::create_module]. The
+/// columns of the table are declared by the collection's [ToRow] implementation, and the
+/// rowid of each row is provided by the collection.
+///
+/// Queries which constrain the rowid using `=`, `>`, `>=`, `<`, or `<=` are answered
+/// without scanning the entire collection. Rows are always returned in rowid order.
+///
+/// If the collection's row type implements [FromRow], the table can be made writable by
+/// registering it with [update_module](CollectionVTab::update_module) instead of
+/// [module](CollectionVTab::module).
+///
+/// The implementation of [Collection] for `Vec` uses the index of each element as its
+/// rowid, so only the last row can be removed. A statement which deletes any other row,
+/// including a plain `DELETE FROM` of a table with more than one row, fails. Use a
+/// `BTreeMap` keyed by rowid for tables which need to support arbitrary deletes.
+///
+/// # Examples
+///
+/// ```no_run
+/// use sqlite3_ext::{vtab::*, *};
+/// use std::{cell::RefCell, rc::Rc};
+///
+/// #[derive(ToRow)]
+/// struct Person {
+///     name: String,
+///     age: i64,
+/// }
+///
+/// fn init(db: &Connection) -> Result<()> {
+///     let people = Rc::new(RefCell::new(vec![Person {
+///         name: "Alice".to_owned(),
+///         age: 30,
+///     }]));
+///     db.create_module("people", CollectionVTab::module(), people.clone())?;
+///     db.query_row("SELECT age FROM people WHERE name = 'Alice'", (), |_| Ok(()))?;
+///     Ok(())
+/// }
+/// ```
+pub struct CollectionVTab<'vtab, C: Collection> {
+    data: &'vtab RefCell<C>,
+}
+
+impl<'vtab, C: Collection + 'vtab> CollectionVTab<'vtab, C> {
+    /// Return a read-only [Module] for this virtual table.
+    pub fn module() -> EponymousModule<'vtab, Self> {
+        EponymousModule::new()
+    }
+
+    /// Return a writable [Module] for this virtual table.
+    pub fn update_module() -> EponymousModule<'vtab, Self>
+    where
+        C::Row: FromRow,
+    {
+        EponymousModule::new().with_update()
+    }
+}
+
+// Bits in the index number describing the constraints on the rowid.
+const ROWID_EQ: i32 = 1;
+const ROWID_GT: i32 = 2;
+const ROWID_GE: i32 = 4;
+const ROWID_LT: i32 = 8;
+const ROWID_LE: i32 = 16;
+
+impl<'vtab, C: Collection + 'vtab> VTab<'vtab> for CollectionVTab<'vtab, C> {
+    type Aux = Rc<RefCell<C>>;
+    type Cursor = CollectionCursor<'vtab, C>;
+
+    fn connect(
+        _db: &'vtab VTabConnection,
+        aux: &'vtab Self::Aux,
+        _args: &[&str],
+    ) -> Result<(String, Self)> {
+        Ok((C::Row::schema(), CollectionVTab { data: aux }))
+    }
+
+    fn best_index(&self, index_info: &mut IndexInfo) -> Result<()> {
+        let mut plan = 0;
+        let mut argv: [Option<usize>; 3] = [None; 3];
+        for (i, c) in index_info.constraints().enumerate() {
+            if c.column() >= 0 || !c.usable() {
+                continue;
+            }
+            let (bit, slot) = match c.op() {
+                ConstraintOp::Eq => (ROWID_EQ, 0),
+                ConstraintOp::GT => (ROWID_GT, 1),
+                ConstraintOp::GE => (ROWID_GE, 1),
+                ConstraintOp::LT => (ROWID_LT, 2),
+                ConstraintOp::LE => (ROWID_LE, 2),
+                _ => continue,
+            };
+            if argv[slot].is_none() {
+                plan |= bit;
+                argv[slot] = Some(i);
+            }
+        }
+        if plan & ROWID_EQ != 0 {
+            // An equality constraint makes the range constraints redundant.
+            plan = ROWID_EQ;
+            argv[1] = None;
+            argv[2] = None;
+        }
+        let mut constraints: Vec<_> = index_info.constraints().collect();
+        for (next_idx, i) in argv.into_iter().flatten().enumerate() {
+            // The bounds are not always exact (for example, when comparing against a
+            // non-integer value), so SQLite still needs to double-check them.
+            constraints[i].set_argv_index(Some(next_idx as _));
+        }
+        let len = self.data.borrow().len().max(1) as f64;
+        if plan & ROWID_EQ != 0 {
+            index_info.set_estimated_cost(1.0);
+            index_info.set_estimated_rows(1);
+        } else if plan != 0 {
+            index_info.set_estimated_cost(len / 4.0);
+            index_info.set_estimated_rows((len / 4.0) as i64 + 1);
+        } else {
+            index_info.set_estimated_cost(len);
+            index_info.set_estimated_rows(len as i64);
+        }
+        let mut order_by = index_info.order_by();
+        if let (Some(o), None) = (order_by.next(), order_by.next()) {
+            if o.column() < 0 && !o.desc() {
+                index_info.set_order_by_consumed(true);
+            }
+        }
+        index_info.set_index_num(plan);
+        Ok(())
+    }
+
+    fn open(&'vtab self) -> Result<Self::Cursor> {
+        Ok(CollectionCursor {
+            data: self.data,
+            rowid: None,
+            upper: Bound::Unbounded,
+        })
+    }
+}
+
+impl<'vtab, C: Collection + 'vtab> UpdateVTab<'vtab> for CollectionVTab<'vtab, C>
+where
+    C::Row: FromRow,
+{
+    fn update(&'vtab self, info: &mut ChangeInfo) -> Result<i64> {
+        let mut data = self.data.try_borrow_mut().map_err(|_| SQLITE_LOCKED)?;
+        match info.change_type() {
+            ChangeType::Delete => {
+                data.remove(info.rowid().get_i64())?;
+                Ok(0)
+            }
+            ChangeType::Insert => {
+                let args = info.args_mut();
+                let rowid = if args[0].is_null() {
+                    None
+                } else {
+                    Some(args[0].get_i64())
+                };
+                let row = C::Row::from_row(&mut args[1..])?;
+                data.insert(rowid, row)
+            }
+            ChangeType::Update => {
+                let old_rowid = info.rowid().get_i64();
+                let args = info.args_mut();
+                let new_rowid = args[0].get_i64();
+                let row = C::Row::from_row(&mut args[1..])?;
+                data.replace(old_rowid, new_rowid, row)?;
+                Ok(new_rowid)
+            }
+        }
+    }
+}
+
+/// Cursor for [CollectionVTab].
+///
+/// The cursor only remembers the rowid of the current row, so the collection may be
+/// modified while a query is in progress.
+pub struct CollectionCursor<'vtab, C: Collection> {
+    data: &'vtab RefCell<C>,
+    rowid: Option<i64>,
+    upper: Bound<i64>,
+}
+
+impl<C: Collection> CollectionCursor<'_, C> {
+    fn seek(&mut self, lower: Bound<i64>) {
+        self.rowid = self
+            .data
+            .borrow()
+            .first_rowid(lower)
+            .filter(|x| match self.upper {
+                Bound::Unbounded => true,
+                Bound::Included(u) => *x <= u,
+                Bound::Excluded(u) => *x < u,
+            });
+    }
+}
+
+/// Convert a constraint on the rowid into a bound. The returned bound always includes every
+/// rowid which satisfies the constraint, but may include some which do not. Returns Err if
+/// no rowid can satisfy the constraint.
+fn rowid_bound(
+    val: &mut ValueRef,
+    inclusive: bool,
+    lower: bool,
+) -> std::result::Result<Bound<i64>, ()> {
+    match val.numeric_type() {
+        ValueType::Null => Err(()),
+        ValueType::Integer => {
+            let x = val.get_i64();
+            Ok(if inclusive {
+                Bound::Included(x)
+            } else {
+                Bound::Excluded(x)
+            })
+        }
+        ValueType::Float => {
+            let x = val.get_f64();
+            if x.is_nan() {
+                Err(())
+            } else if lower {
+                Ok(Bound::Included(x.ceil() as i64))
+            } else {
+                Ok(Bound::Included(x.floor() as i64))
+            }
+        }
+        // Let SQLite compare against other types.
+        _ => Ok(Bound::Unbounded),
+    }
+}
+
+impl<C: Collection> VTabCursor for CollectionCursor<'_, C> {
+    fn filter(&mut self, plan: i32, _: Option<&str>, args: &mut [&mut ValueRef]) -> Result<()> {
+        let mut args = args.iter_mut();
+        let mut lower = Bound::Unbounded;
+        self.upper = Bound::Unbounded;
+        let bounds = (|| {
+            if plan & ROWID_EQ != 0 {
+                let val = args.next().unwrap();
+                let x = match val.numeric_type() {
+                    ValueType::Integer => val.get_i64(),
+                    // Only a float which is exactly an integer can equal a rowid.
+                    ValueType::Float => {
+                        let x = val.get_f64();
+                        if x.fract() != 0.0 || x < i64::MIN as f64 || x >= -(i64::MIN as f64) {
+                            return Err(());
+                        }
+                        x as i64
+                    }
+                    _ => return Err(()),
+                };
+                lower = Bound::Included(x);
+                self.upper = Bound::Included(x);
+            }
+            if plan & (ROWID_GT | ROWID_GE) != 0 {
+                lower = rowid_bound(args.next().unwrap(), plan & ROWID_GE != 0, true)?;
+            }
+            if plan & (ROWID_LT | ROWID_LE) != 0 {
+                self.upper = rowid_bound(args.next().unwrap(), plan & ROWID_LE != 0, false)?;
+            }
+            Ok(())
+        })();
+        match bounds {
+            Ok(()) => self.seek(lower),
+            Err(()) => self.rowid = None,
+        }
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<()> {
+        if let Some(rowid) = self.rowid {
+            self.seek(Bound::Excluded(rowid));
+        }
+        Ok(())
+    }
+
+    fn eof(&mut self) -> bool {
+        self.rowid.is_none()
+    }
+
+    fn column(&mut self, idx: usize, context: &ColumnContext) -> Result<()> {
+        let data = self.data.borrow();
+        match self.rowid.and_then(|r| data.get(r)) {
+            Some(row) => row.column(idx, context),
+            // The row was removed while the cursor pointed to it.
+            None => context.set_result(()),
+        }
+    }
+
+    fn rowid(&mut self) -> Result<i64> {
+        self.rowid.ok_or(SQLITE_MISUSE)
+    }
+}
@@ -0,0 +1,171 @@
+//! Virtual table cursors driven by generator functions.
+
+use super::*;
+use fallible_iterator::FallibleIterator;
+
+/// A source of rows for [GeneratorCursor].
+///
+/// Each call returns the next row, or None once the source is exhausted. Use
+/// [GeneratorCursor::from_iterator] and related functions to create a generator from other
+/// types of sources.
+pub type Generator<'a, R> = Box<dyn FnMut() -> Result<Option<R>> + 'a>;
+
+type GeneratorFactory<'a, R> =
+    Box<dyn FnMut(i32, Option<&str>, &mut [&mut ValueRef]) -> Result<Generator<'a, R>> + 'a>;
+
+/// A [VTabCursor] which pulls rows from a [Generator].
+///
+/// The cursor is created with a factory function, which is called with the parameters of
+/// [VTabCursor::filter] each time a query begins and returns a generator for the query's
+/// rows. The cursor buffers the current row and implements the remaining cursor methods
+/// using it. Rows are assigned sequential rowids beginning with 1.
+///
+/// # Examples
+///
+/// ```no_run
+/// use sqlite3_ext::{vtab::*, *};
+///
+/// #[derive(VTabSchema)]
+/// struct Line {
+///     line: String,
+/// }
+///
+/// #[sqlite3_ext_vtab(EponymousModule)]
+/// struct Lines {}
+///
+/// impl<'vtab> VTab<'vtab> for Lines {
+///     type Aux = ();
+///     type Cursor = GeneratorCursor<'vtab, Line>;
+///
+///     fn connect(_: &VTabConnection, _: &(), _: &[&str]) -> Result<(String, Self)> {
+///         Ok((Line::DECLARATION.to_owned(), Lines {}))
+///     }
+///
+///     fn best_index(&self, _: &mut IndexInfo) -> Result<()> {
+///         Ok(())
+///     }
+///
+///     fn open(&self) -> Result<Self::Cursor> {
+///         Ok(GeneratorCursor::new(|_, _, _| {
+///             let text = "first line\nsecond line";
+///             Ok(GeneratorCursor::from_iterator(text.lines().map(|line| Line {
+///                 line: line.to_owned(),
+///             })))
+///         }))
+///     }
+/// }
+/// ```
+pub struct GeneratorCursor<'a, R> {
+    factory: GeneratorFactory<'a, R>,
+    generator: Option<Generator<'a, R>>,
+    current: Option<R>,
+    rowid: i64,
+}
+
+impl<'a, R: 'a> GeneratorCursor<'a, R> {
+    /// Create a cursor which calls the provided function to begin each query.
+    pub fn new<F>(factory: F) -> Self
+    where
+        F: FnMut(i32, Option<&str>, &mut [&mut ValueRef]) -> Result<Generator<'a, R>> + 'a,
+    {
+        GeneratorCursor {
+            factory: Box::new(factory),
+            generator: None,
+            current: None,
+            rowid: 0,
+        }
+    }
+
+    /// Create a generator from a function.
+    pub fn from_fn<F>(f: F) -> Generator<'a, R>
+    where
+        F: FnMut() -> Result<Option<R>> + 'a,
+    {
+        Box::new(f)
+    }
+
+    /// Create a generator from an iterator of rows.
+    pub fn from_iterator<I>(iter: I) -> Generator<'a, R>
+    where
+        I: IntoIterator<Item = R>,
+        I::IntoIter: 'a,
+    {
+        let mut iter = iter.into_iter();
+        Box::new(move || Ok(iter.next()))
+    }
+
+    /// Create a generator from a [FallibleIterator] of rows.
+    pub fn from_fallible_iterator<I>(iter: I) -> Generator<'a, R>
+    where
+        I: FallibleIterator<Item = R, Error = Error> + 'a,
+    {
+        let mut iter = iter;
+        Box::new(move || iter.next())
+    }
+
+    /// Create a generator from a [Stream](futures::Stream) of rows. Each row is awaited
+    /// using a blocking executor.
+    ///
+    /// Requires the `futures` feature.
+    #[cfg(feature = "futures")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "futures")))]
+    pub fn from_stream<S>(stream: S) -> Generator<'a, R>
+    where
+        S: futures::Stream<Item = Result<R>> + 'a,
+    {
+        let mut iter = futures::executor::block_on_stream(Box::pin(stream));
+        Box::new(move || iter.next().transpose())
+    }
+
+    /// Return the current row, or None if the cursor is at EOF.
+    pub fn current(&self) -> Option<&R> {
+        self.current.as_ref()
+    }
+
+    fn advance(&mut self) -> Result<()> {
+        self.current = match self.generator.as_mut() {
+            Some(g) => g()?,
+            None => None,
+        };
+        if self.current.is_none() {
+            // Release the resources held by the generator as soon as possible.
+            self.generator = None;
+        }
+        self.rowid += 1;
+        Ok(())
+    }
+}
+
+impl<'a, R: ToRow + 'a> VTabCursor for GeneratorCursor<'a, R> {
+    fn filter(
+        &mut self,
+        index_num: i32,
+        index_str: Option<&str>,
+        args: &mut [&mut ValueRef],
+    ) -> Result<()> {
+        self.generator = None;
+        self.current = None;
+        self.generator = Some((self.factory)(index_num, index_str, args)?);
+        self.rowid = 0;
+        self.advance()
+    }
+
+    fn next(&mut self) -> Result<()> {
+        self.advance()
+    }
+
+    fn eof(&mut self) -> bool {
+        self.current.is_none()
+    }
+
+    fn column(&mut self, idx: usize, context: &ColumnContext) -> Result<()> {
+        match &self.current {
+            Some(row) => row.column(idx, context),
+            None => Err(SQLITE_MISUSE),
+        }
+    }
+
+    fn rowid(&mut self) -> Result<i64> {
+        Ok(self.rowid)
+    }
+}
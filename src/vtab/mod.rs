@@ -22,18 +22,28 @@
 //! - [FindFunctionVTab] indicates that the table overrides certain SQL functions when they
 //!   operate on the table.
 //! - [RenameVTab] indicates that the table supports ALTER TABLE RENAME TO.
+//!
+//! For the common case of exposing an in-memory Rust collection to SQL, [CollectionVTab]
+//! provides a complete implementation. For rows produced on demand, [GeneratorCursor]
+//! implements [VTabCursor] on top of a function or iterator.
 
 use super::{
     ffi, function::ToContextResult, sqlite3_match_version, types::*, value::*, Connection,
 };
+pub use collection::*;
 pub use function::*;
+pub use generator::*;
 pub use index_info::*;
 pub use module::*;
+pub use schema::*;
 use std::{ffi::c_void, ops::Deref, slice};
 
+mod collection;
 mod function;
+mod generator;
 mod index_info;
 mod module;
+mod schema;
 pub(crate) mod stubs;
 
 pub type DisconnectResult<T> = std::result::Result<(), (T, Error)>;
@@ -132,6 +142,15 @@ pub trait CreateVTab<'vtab>: VTab<'vtab> {
 
 /// A virtual table that supports INSERT/UPDATE/DELETE.
 pub trait UpdateVTab<'vtab>: VTab<'vtab> {
+    /// For WITHOUT ROWID virtual tables, the index of the PRIMARY KEY column.
+    ///
+    /// SQLite requires writable WITHOUT ROWID virtual tables to declare a PRIMARY KEY
+    /// consisting of exactly one column. Setting this value allows
+    /// [ChangeInfo::new_primary_key] to locate the new PRIMARY KEY of a row being inserted.
+    /// When using [VTabSchema](sqlite3_ext_macro::VTabSchema), this can be set to
+    /// [VTabSchema::PRIMARY_KEY].
+    const PRIMARY_KEY: Option<usize> = None;
+
     /// Modify a single row in the virtual table. The info parameter may be used to
     /// determine the type of change being performed by this update.
     ///
@@ -249,7 +268,13 @@ pub trait VTabCursor {
     fn column(&mut self, idx: usize, context: &ColumnContext) -> Result<()>;
 
     /// Fetch the rowid for the current row.
-    fn rowid(&mut self) -> Result<i64>;
+    ///
+    /// SQLite never calls this method for WITHOUT ROWID virtual tables, so those tables do
+    /// not need to implement it. The default implementation returns
+    /// Err([SQLITE_MISUSE]).
+    fn rowid(&mut self) -> Result<i64> {
+        Err(SQLITE_MISUSE)
+    }
 }
 
 /// Implementation of the transaction type for a virtual table.
@@ -409,6 +434,7 @@ pub struct ChangeInfo {
     db: *mut ffi::sqlite3,
     argc: usize,
     argv: *mut *mut ValueRef,
+    primary_key: Option<usize>,
 }
 
 impl ChangeInfo {
@@ -443,6 +469,33 @@ impl ChangeInfo {
         unsafe { &mut **self.argv }
     }
 
+    /// Returns the identity of the row being deleted or updated, or None for an INSERT.
+    ///
+    /// For WITHOUT ROWID virtual tables, this is the old value of the PRIMARY KEY column.
+    /// Otherwise, it is the old rowid.
+    pub fn old_primary_key(&self) -> Option<&ValueRef> {
+        match self.change_type() {
+            ChangeType::Insert => None,
+            _ => Some(self.rowid()),
+        }
+    }
+
+    /// Returns the identity of the row being inserted or updated, or None for a DELETE.
+    ///
+    /// For WITHOUT ROWID virtual tables which set [UpdateVTab::PRIMARY_KEY], this is the
+    /// new value of the PRIMARY KEY column. Otherwise, it is the new rowid, which may be
+    /// NULL for an INSERT if the virtual table must generate a rowid.
+    pub fn new_primary_key(&self) -> Option<&ValueRef> {
+        let args = match self.change_type() {
+            ChangeType::Delete => return None,
+            _ => self.args(),
+        };
+        match self.primary_key {
+            Some(idx) => args.get(idx + 1).copied(),
+            None => Some(args[0]),
+        }
+    }
+
     /// Returns the arguments for an INSERT or UPDATE. The meaning of the first element in
     /// this slice depends on the type of change being performed:
     ///
@@ -470,6 +523,61 @@ impl ChangeInfo {
         unsafe { slice::from_raw_parts_mut(self.argv.offset(1) as _, self.argc - 1) }
     }
 
+    /// Decode the change into a [Change].
+    ///
+    /// This method assumes that the virtual table uses integer rowids. For WITHOUT ROWID
+    /// virtual tables, use [old_primary_key](Self::old_primary_key) and
+    /// [new_primary_key](Self::new_primary_key) instead.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use sqlite3_ext::{vtab::*, *};
+    ///
+    /// fn update(info: &mut ChangeInfo) -> Result<i64> {
+    ///     match info.change() {
+    ///         Change::Update { new_rowid, values, .. } => {
+    ///             for (idx, val) in values.into_iter().enumerate() {
+    ///                 if let ColumnValue::Set(val) = val {
+    ///                     println!("row {}: column {} changed to {:?}", new_rowid, idx, val);
+    ///                 }
+    ///             }
+    ///             Ok(new_rowid)
+    ///         }
+    ///         _ => Err(SQLITE_READONLY),
+    ///     }
+    /// }
+    /// ```
+    pub fn change(&mut self) -> Change<'_> {
+        match self.change_type() {
+            ChangeType::Delete => Change::Delete {
+                rowid: self.rowid().get_i64(),
+            },
+            ChangeType::Insert => {
+                let args = self.args_mut();
+                let (rowid, values) = args.split_first_mut().unwrap();
+                Change::Insert {
+                    rowid: if rowid.is_null() {
+                        None
+                    } else {
+                        Some(rowid.get_i64())
+                    },
+                    values: ColumnValue::from_args(values),
+                }
+            }
+            ChangeType::Update => {
+                let old_rowid = self.rowid().get_i64();
+                let args = self.args_mut();
+                let (new_rowid, values) = args.split_first_mut().unwrap();
+                Change::Update {
+                    old_rowid,
+                    new_rowid: new_rowid.get_i64(),
+                    values: ColumnValue::from_args(values),
+                }
+            }
+        }
+    }
+
     /// Return the ON CONFLICT mode of the current SQL statement. In order for this method
     /// to be useful, the virtual table needs to have previously enabled ON CONFLICT
     /// support using [VTabConnection::enable_constraints].
@@ -508,6 +616,61 @@ pub enum ChangeType {
     Update,
 }
 
+/// A decoded INSERT/UPDATE/DELETE on a virtual table. See [ChangeInfo::change].
+#[derive(Debug)]
+pub enum Change<'a> {
+    /// An SQL INSERT. If rowid is None, the virtual table must generate a rowid for the
+    /// new row.
+    Insert {
+        rowid: Option<i64>,
+        values: Vec<ColumnValue<'a>>,
+    },
+    /// An SQL UPDATE. The old_rowid and new_rowid will differ if the UPDATE changes the
+    /// rowid.
+    Update {
+        old_rowid: i64,
+        new_rowid: i64,
+        values: Vec<ColumnValue<'a>>,
+    },
+    /// An SQL DELETE.
+    Delete { rowid: i64 },
+}
+
+/// The value of a single column in a [Change].
+///
+/// The values appear in the order the columns were declared in the virtual table's
+/// schema.
+#[derive(Debug)]
+pub enum ColumnValue<'a> {
+    /// The new value of the column.
+    Set(&'a mut ValueRef),
+    /// The column is not modified by this UPDATE, and its underlying value must not be
+    /// changed. See [ValueRef::nochange] for the circumstances in which this occurs.
+    Unchanged,
+}
+
+impl<'a> ColumnValue<'a> {
+    fn from_args(args: &'a mut [&mut ValueRef]) -> Vec<Self> {
+        args.iter_mut()
+            .map(|v| {
+                if v.nochange() {
+                    ColumnValue::Unchanged
+                } else {
+                    ColumnValue::Set(v)
+                }
+            })
+            .collect()
+    }
+
+    /// Return the new value of the column, or None if the column is unchanged.
+    pub fn value(&mut self) -> Option<&mut ValueRef> {
+        match self {
+            ColumnValue::Set(v) => Some(v),
+            ColumnValue::Unchanged => None,
+        }
+    }
+}
+
 /// Indicates the ON CONFLICT mode for the SQL statement currently being executed.
 ///
 /// An [UpdateVTab] which has used [VTabConnection::enable_constraints] can examine this value
@@ -585,4 +748,10 @@ impl ColumnContext {
         unsafe { val.assign_to(self.as_ptr()) };
         Ok(())
     }
+
+    /// Assign the column numbered idx of the given row to the column. See [VTabSchema] for
+    /// an example.
+    pub fn set_row_field<R: ToRow>(&self, row: &R, idx: usize) -> Result<()> {
+        row.column(idx, self)
+    }
 }
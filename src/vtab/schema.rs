@@ -0,0 +1,75 @@
+//! Declaring the columns of virtual tables.
+
+use super::*;
+
+/// A type which can be used as a row of a virtual table.
+///
+/// This trait is normally implemented using the [ToRow](sqlite3_ext_macro::ToRow) derive
+/// macro, which declares one column for each named field of the struct, in declaration
+/// order. The declared type of each column is inferred from the type of the field.
+///
+/// # Examples
+///
+/// ```no_run
+/// use sqlite3_ext::{vtab::*, *};
+///
+/// #[derive(ToRow)]
+/// struct Person {
+///     name: String,
+///     age: i64,
+/// }
+///
+/// assert_eq!(Person::schema(), "CREATE TABLE x ( \"name\" TEXT, \"age\" INTEGER )");
+/// ```
+pub trait ToRow {
+    /// Return the CREATE TABLE statement which declares the columns of this row.
+    fn schema() -> String;
+
+    /// Assign the column numbered idx to the context. The indexes correspond to the order
+    /// the columns were declared by [schema](ToRow::schema).
+    fn column(&self, idx: usize, context: &ColumnContext) -> Result<()>;
+}
+
+/// A row type with a statically known virtual table declaration.
+///
+/// This trait is implemented using the [VTabSchema](sqlite3_ext_macro::VTabSchema) derive
+/// macro. The declaration can be returned from [VTab::connect], and the row can be used
+/// to implement [VTabCursor::column] with [ColumnContext::set_row_field]. Because both
+/// are generated from the same struct, the column indexes always agree with the schema.
+///
+/// # Examples
+///
+/// ```no_run
+/// use sqlite3_ext::{vtab::*, *};
+///
+/// #[derive(VTabSchema)]
+/// struct Row {
+///     value: i64,
+///     #[sqlite(hidden)]
+///     start: i64,
+/// }
+///
+/// struct Cursor {
+///     row: Row,
+/// }
+///
+/// impl Cursor {
+///     fn column(&mut self, idx: usize, c: &ColumnContext) -> Result<()> {
+///         if idx == Row::COLUMN_START {
+///             // Handle the hidden column specially.
+///         }
+///         c.set_row_field(&self.row, idx)
+///     }
+/// }
+/// ```
+pub trait VTabSchema: ToRow {
+    /// The CREATE TABLE statement which declares the virtual table.
+    const DECLARATION: &'static str;
+
+    /// The names of the columns, in declaration order.
+    const COLUMNS: &'static [&'static str];
+
+    /// For WITHOUT ROWID tables with a single-column PRIMARY KEY, the index of that column.
+    /// See [UpdateVTab::PRIMARY_KEY].
+    const PRIMARY_KEY: Option<usize>;
+}
@@ -247,6 +247,7 @@ pub unsafe extern "C" fn vtab_update<'vtab, T: UpdateVTab<'vtab> + 'vtab>(
         db: vtab.db,
         argc: argc as _,
         argv: argv as _,
+        primary_key: T::PRIMARY_KEY,
     };
     match vtab.vtab.update(&mut context) {
         Ok(rowid) => {
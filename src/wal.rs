@@ -0,0 +1,258 @@
+use super::{ffi, sqlite3_require_version, types::*, Connection};
+use std::{
+    ffi::{c_void, CStr},
+    os::raw::{c_char, c_int},
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+/// The type of checkpoint to run. See [Connection::wal_checkpoint].
+///
+/// For more information, see [the SQLite documentation](https://www.sqlite.org/c3ref/wal_checkpoint_v2.html).
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum CheckpointMode {
+    /// Checkpoint as many frames as possible without waiting for any database readers or
+    /// writers to finish.
+    Passive,
+    /// Wait for all writers to finish, then checkpoint every frame in the log. New writers
+    /// are blocked while the checkpoint is running.
+    Full,
+    /// Like [Full](CheckpointMode::Full), but additionally wait until all readers are
+    /// reading from the database file, so that the next writer restarts the log file from
+    /// the beginning.
+    Restart,
+    /// Like [Restart](CheckpointMode::Restart), but additionally truncate the log file to
+    /// zero bytes.
+    ///
+    /// Requires SQLite 3.8.8.
+    Truncate,
+}
+
+/// The outcome of a checkpoint. See [Connection::wal_checkpoint].
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct CheckpointInfo {
+    /// The number of frames in the write-ahead log, or -1 if the database is not in WAL
+    /// mode.
+    pub log_frames: i32,
+    /// The number of frames in the write-ahead log which have been checkpointed, or -1 if
+    /// the database is not in WAL mode.
+    pub checkpointed_frames: i32,
+    /// True if the checkpoint could not run to completion because another connection was
+    /// using the database. This corresponds to SQLITE_BUSY.
+    pub busy: bool,
+}
+
+impl CheckpointMode {
+    #[cfg_attr(not(modern_sqlite), allow(unused))]
+    fn to_sqlite(self) -> Result<c_int> {
+        match self {
+            CheckpointMode::Passive => Ok(ffi::SQLITE_CHECKPOINT_PASSIVE),
+            CheckpointMode::Full => Ok(ffi::SQLITE_CHECKPOINT_FULL),
+            CheckpointMode::Restart => Ok(ffi::SQLITE_CHECKPOINT_RESTART),
+            CheckpointMode::Truncate => {
+                sqlite3_require_version!(3_008_008, Ok(ffi::SQLITE_CHECKPOINT_TRUNCATE))
+            }
+        }
+    }
+}
+
+impl Connection {
+    /// Run a checkpoint on the write-ahead log of the given database, or on all attached
+    /// databases if schema is None.
+    ///
+    /// If the checkpoint cannot run to completion because another connection is using the
+    /// database, SQLite reports SQLITE_BUSY. This is not treated as an error; instead, the
+    /// returned [CheckpointInfo] has busy set, along with the frame counts of the partial
+    /// checkpoint.
+    ///
+    /// Requires SQLite 3.7.6. [CheckpointMode::Truncate] requires SQLite 3.8.8.
+    pub fn wal_checkpoint(
+        &self,
+        schema: Option<&str>,
+        mode: CheckpointMode,
+    ) -> Result<CheckpointInfo> {
+        let _ = (schema, mode);
+        sqlite3_require_version!(3_007_006, {
+            let mode = mode.to_sqlite()?;
+            let schema = schema.map(std::ffi::CString::new).transpose()?;
+            let mut log_frames: c_int = 0;
+            let mut checkpointed: c_int = 0;
+            let guard = self.lock();
+            let rc = unsafe {
+                ffi::sqlite3_wal_checkpoint_v2(
+                    self.as_mut_ptr(),
+                    schema.as_ref().map_or(std::ptr::null(), |s| s.as_ptr()),
+                    mode,
+                    &mut log_frames,
+                    &mut checkpointed,
+                )
+            };
+            let busy = rc == ffi::SQLITE_BUSY;
+            if !busy {
+                Error::from_sqlite_desc(rc, guard)?;
+            }
+            Ok(CheckpointInfo {
+                log_frames,
+                checkpointed_frames: checkpointed,
+                busy,
+            })
+        })
+    }
+
+    /// Configure the database to automatically run a passive checkpoint when a transaction
+    /// causes the write-ahead log to reach the given number of pages. A value of 0 or less
+    /// disables automatic checkpoints.
+    ///
+    /// This replaces any hook installed with [set_wal_hook](Connection::set_wal_hook).
+    ///
+    /// Requires SQLite 3.7.0.
+    pub fn set_wal_autocheckpoint(&self, pages: i32) -> Result<()> {
+        let _ = pages;
+        sqlite3_require_version!(3_007_000, {
+            let guard = self.lock();
+            let rc = unsafe { ffi::sqlite3_wal_autocheckpoint(self.as_mut_ptr(), pages) };
+            Error::from_sqlite_desc(rc, guard)
+        })
+    }
+
+    /// Register a callback which is invoked each time a transaction is committed to a
+    /// database in WAL mode. The callback receives the name of the database which was
+    /// written to and the number of pages currently in the write-ahead log. If the callback
+    /// returns an error, it is reported as the result of the statement which committed the
+    /// transaction, even though the transaction has already been committed.
+    ///
+    /// This replaces the automatic checkpoint configured by
+    /// [set_wal_autocheckpoint](Connection::set_wal_autocheckpoint). The callback can run
+    /// checkpoints itself using [wal_checkpoint](Connection::wal_checkpoint).
+    ///
+    /// If the callback panics, the panic is caught and reported to SQLite as an error.
+    ///
+    /// Note: the provided function and any captured variables will be leaked. SQLite does
+    /// not provide any facilities for cleaning up this data.
+    ///
+    /// Requires SQLite 3.7.0.
+    pub fn set_wal_hook<F: Fn(&str, i32) -> Result<()> + 'static>(&self, func: F) -> Result<()> {
+        let _ = func;
+        sqlite3_require_version!(3_007_000, {
+            let func = Box::new(func);
+            let _guard = self.lock();
+            unsafe {
+                ffi::sqlite3_wal_hook(
+                    self.as_mut_ptr(),
+                    Some(wal_hook::<F>),
+                    Box::into_raw(func) as _,
+                );
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg_attr(not(modern_sqlite), allow(unused))]
+unsafe extern "C" fn wal_hook<F: Fn(&str, i32) -> Result<()> + 'static>(
+    user_data: *mut c_void,
+    _db: *mut ffi::sqlite3,
+    name: *const c_char,
+    pages: c_int,
+) -> c_int {
+    let func = &*(user_data as *const F);
+    let name = match CStr::from_ptr(name).to_str() {
+        Ok(x) => x,
+        Err(_) => return ffi::SQLITE_ERROR,
+    };
+    // Unwinding across the FFI boundary is undefined behavior.
+    match catch_unwind(AssertUnwindSafe(|| func(name, pages))) {
+        Ok(Ok(())) => ffi::SQLITE_OK,
+        Ok(Err(Error::Sqlite(rc, _))) => rc,
+        Ok(Err(_)) | Err(_) => ffi::SQLITE_ERROR,
+    }
+}
+
+#[cfg(all(modern_sqlite, test, feature = "static"))]
+mod test {
+    use crate::test_helpers::prelude::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn wal_checkpoint() -> Result<()> {
+        let temp = TempDatabase::new("wal_checkpoint");
+        let db = Database::open(&temp.path)?;
+        db.query_row("PRAGMA journal_mode = WAL", (), |_| Ok(()))?;
+        db.set_wal_autocheckpoint(0)?;
+        db.execute("CREATE TABLE tbl(col)", ())?;
+        db.execute("INSERT INTO tbl VALUES (1)", ())?;
+        let info = db.wal_checkpoint(None, CheckpointMode::Passive)?;
+        assert!(info.log_frames > 0);
+        assert_eq!(info.log_frames, info.checkpointed_frames);
+        assert!(!info.busy);
+        let info = db.wal_checkpoint(Some("main"), CheckpointMode::Truncate)?;
+        assert_eq!(
+            info,
+            CheckpointInfo {
+                log_frames: 0,
+                checkpointed_frames: 0,
+                busy: false
+            }
+        );
+        assert!(db
+            .wal_checkpoint(Some("nonexistent"), CheckpointMode::Full)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn wal_checkpoint_busy() -> Result<()> {
+        let temp = TempDatabase::new("wal_checkpoint_busy");
+        let db = Database::open(&temp.path)?;
+        db.query_row("PRAGMA journal_mode = WAL", (), |_| Ok(()))?;
+        db.set_wal_autocheckpoint(0)?;
+        db.execute("CREATE TABLE tbl(col)", ())?;
+        let reader = Database::open(&temp.path)?;
+        reader.execute("BEGIN", ())?;
+        reader.query_row("SELECT COUNT(*) FROM tbl", (), |_| Ok(()))?;
+        db.execute("INSERT INTO tbl VALUES (1)", ())?;
+        let info = db.wal_checkpoint(None, CheckpointMode::Restart)?;
+        assert!(info.busy);
+        assert!(info.log_frames > 0);
+        assert!(info.checkpointed_frames < info.log_frames);
+        reader.execute("COMMIT", ())?;
+        Ok(())
+    }
+
+    #[test]
+    fn wal_checkpoint_memory() -> Result<()> {
+        let h = TestHelpers::new();
+        let info = h.db.wal_checkpoint(None, CheckpointMode::Passive)?;
+        assert_eq!((info.log_frames, info.checkpointed_frames), (-1, -1));
+        Ok(())
+    }
+
+    #[test]
+    fn wal_hook() -> Result<()> {
+        let temp = TempDatabase::new("wal_hook");
+        let db = Database::open(&temp.path)?;
+        db.query_row("PRAGMA journal_mode = WAL", (), |_| Ok(()))?;
+        let calls = Rc::new(RefCell::new(vec![]));
+        let calls_inner = calls.clone();
+        db.set_wal_hook(move |name, pages| {
+            calls_inner.borrow_mut().push((name.to_owned(), pages));
+            Ok(())
+        })?;
+        db.execute("CREATE TABLE tbl(col)", ())?;
+        db.execute("INSERT INTO tbl VALUES (1)", ())?;
+        let calls = calls.borrow();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].0, "main");
+        assert!(calls[1].1 > calls[0].1);
+        Ok(())
+    }
+
+    #[test]
+    fn wal_hook_panic() -> Result<()> {
+        let temp = TempDatabase::new("wal_hook_panic");
+        let db = Database::open(&temp.path)?;
+        db.query_row("PRAGMA journal_mode = WAL", (), |_| Ok(()))?;
+        db.set_wal_hook(|_, _| panic!("wal hook"))?;
+        assert!(db.execute("CREATE TABLE tbl(col)", ()).is_err());
+        Ok(())
+    }
+}
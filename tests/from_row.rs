@@ -0,0 +1,119 @@
+//! Test cases for derive(FromRow).
+use sqlite3_ext::*;
+
+#[derive(FromRow, Debug, PartialEq)]
+struct Person {
+    name: String,
+    #[sqlite(rename = "years")]
+    age: i64,
+    email: Option<String>,
+    r#type: Value,
+}
+
+#[derive(FromRow, Debug, PartialEq)]
+struct Pair(u32, Option<Vec<u8>>);
+
+fn setup() -> Result<Database> {
+    let db = Database::open(":memory:")?;
+    db.execute_batch(
+        "CREATE TABLE people(name, years, email, type); \
+         INSERT INTO people VALUES ('alice', 30, NULL, 1), ('bob', 25, 'bob@example.com', 'x');",
+    )?;
+    Ok(db)
+}
+
+#[test]
+fn by_name() -> Result<()> {
+    let db = setup()?;
+    // Columns are selected out of order to show that they are matched by name.
+    let person: Person = db.query_one(
+        "SELECT email, type, years, name FROM people WHERE name = ?",
+        ["alice"],
+    )?;
+    assert_eq!(
+        person,
+        Person {
+            name: "alice".to_owned(),
+            age: 30,
+            email: None,
+            r#type: Value::Integer(1),
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn missing_column() -> Result<()> {
+    let db = setup()?;
+    let err = db
+        .query_one::<Person, _>("SELECT name, years FROM people", ())
+        .unwrap_err();
+    assert_eq!(err.to_string(), "no such column: email");
+    Ok(())
+}
+
+#[test]
+fn by_position() -> Result<()> {
+    let db = setup()?;
+    let pairs: Vec<Pair> = db
+        .prepare("SELECT years, email FROM people ORDER BY years")?
+        .query_map(())?
+        .collect()?;
+    assert_eq!(
+        pairs,
+        vec![Pair(25, Some(b"bob@example.com".to_vec())), Pair(30, None)]
+    );
+    Ok(())
+}
+
+#[test]
+fn query_map() -> Result<()> {
+    let db = setup()?;
+    let mut stmt = db.prepare("SELECT * FROM people WHERE years > ? ORDER BY name")?;
+    let names: Vec<String> = stmt
+        .query_map::<Person, _>([20])?
+        .map(|p| Ok(p.name))
+        .collect()?;
+    assert_eq!(names, vec!["alice", "bob"]);
+    let names: Vec<String> = stmt
+        .query_map::<Person, _>([27])?
+        .map(|p| Ok(p.name))
+        .collect()?;
+    assert_eq!(names, vec!["alice"]);
+    Ok(())
+}
+
+#[derive(VTabSchema, FromRow, Params, Debug, PartialEq)]
+#[sqlite(without_rowid)]
+struct Setting {
+    #[sqlite(primary_key)]
+    key: String,
+    #[sqlite(type = "INT")]
+    value: i64,
+    #[sqlite(hidden)]
+    scope: Option<String>,
+}
+
+#[test]
+fn with_vtab_schema() -> Result<()> {
+    use sqlite3_ext::vtab::VTabSchema;
+
+    assert_eq!(
+        Setting::DECLARATION,
+        "CREATE TABLE x ( \"key\" TEXT, \"value\" INT, \"scope\" TEXT HIDDEN, PRIMARY KEY (\"key\") ) WITHOUT ROWID"
+    );
+    let db = Database::open(":memory:")?;
+    db.execute("CREATE TABLE settings(key, value, scope)", ())?;
+    let setting = || Setting {
+        key: "a".to_owned(),
+        value: 1,
+        scope: None,
+    };
+    db.execute(
+        "INSERT INTO settings VALUES (:key, :value, :scope)",
+        setting(),
+    )?;
+    let ret: Setting = db.query_one("SELECT scope, value, key FROM settings", ())?;
+    assert_eq!(ret, setting());
+    Ok(())
+}
@@ -0,0 +1,74 @@
+//! Test cases for derive(Params).
+use sqlite3_ext::{
+    query::{Statement, ToParam, ToSql},
+    *,
+};
+
+#[derive(Params)]
+struct NewUser<'a> {
+    name: &'a str,
+    #[sqlite(rename = "years")]
+    age: i64,
+    email: Option<&'a str>,
+}
+
+#[derive(Params)]
+struct Lookup {
+    id: i64,
+}
+
+struct Point {
+    x: i64,
+    y: i64,
+}
+
+impl ToSql for Point {
+    fn bind_sql(&self, stmt: &mut Statement, position: i32) -> Result<()> {
+        format!("{},{}", self.x, self.y).bind_param(stmt, position)
+    }
+}
+
+#[derive(Params)]
+struct Marker<'a> {
+    at: &'a Point,
+}
+
+#[test]
+fn derive() -> Result<()> {
+    let db = Database::open(":memory:")?;
+    db.execute("CREATE TABLE users(name, age, email)", ())?;
+    db.execute(
+        "INSERT INTO users VALUES (:name, @years, $email)",
+        NewUser {
+            name: "alice",
+            age: 30,
+            email: None,
+        },
+    )?;
+    let row = db.query_row("SELECT * FROM users", (), |r| {
+        Ok((r[0].get_str()?.to_owned(), r[1].get_i64(), r[2].is_null()))
+    })?;
+    assert_eq!(row, ("alice".to_owned(), 30, true));
+    Ok(())
+}
+
+#[test]
+fn to_sql_field() -> Result<()> {
+    let db = Database::open(":memory:")?;
+    let at = Point { x: 1, y: 2 };
+    let ret = db.query_row("SELECT :at", Marker { at: &at }, |r| {
+        Ok(r[0].get_str()?.to_owned())
+    })?;
+    assert_eq!(ret, "1,2");
+    Ok(())
+}
+
+#[test]
+fn missing_parameter() -> Result<()> {
+    let db = Database::open(":memory:")?;
+    let err = db
+        .query_row("SELECT :key", Lookup { id: 1 }, |_| Ok(()))
+        .unwrap_err();
+    assert_eq!(err.to_string(), "no such parameter: id");
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use sqlite3_ext::{query::*, *};
+
+fn statement_outlives_database() -> Result<()> {
+    let stmt: Statement;
+    {
+        let db = Database::open(":memory:")?;
+        stmt = db.prepare("SELECT 1")?;
+    }
+    assert_eq!(stmt.column_count(), 1);
+    Ok(())
+}
+
+fn main() {}
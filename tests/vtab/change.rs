@@ -0,0 +1,122 @@
+//! Test cases for ChangeInfo::change.
+use sqlite3_ext::{vtab::*, *};
+use std::cell::RefCell;
+
+#[sqlite3_ext_vtab(EponymousModule, UpdateVTab)]
+struct Table<'vtab> {
+    rows: &'vtab RefCell<Vec<[i64; 2]>>,
+}
+
+struct Cursor<'vtab> {
+    rows: &'vtab RefCell<Vec<[i64; 2]>>,
+    idx: usize,
+}
+
+impl<'vtab> VTab<'vtab> for Table<'vtab> {
+    type Aux = RefCell<Vec<[i64; 2]>>;
+    type Cursor = Cursor<'vtab>;
+
+    fn connect(
+        _db: &'vtab VTabConnection,
+        aux: &'vtab Self::Aux,
+        _args: &[&str],
+    ) -> Result<(String, Self)> {
+        Ok(("CREATE TABLE x ( a, b )".to_owned(), Table { rows: aux }))
+    }
+
+    fn best_index(&self, _index_info: &mut IndexInfo) -> Result<()> {
+        Ok(())
+    }
+
+    fn open(&'vtab self) -> Result<Self::Cursor> {
+        Ok(Cursor {
+            rows: self.rows,
+            idx: 0,
+        })
+    }
+}
+
+impl<'vtab> UpdateVTab<'vtab> for Table<'vtab> {
+    fn update(&'vtab self, info: &mut ChangeInfo) -> Result<i64> {
+        let mut rows = self.rows.borrow_mut();
+        match info.change() {
+            Change::Insert { rowid, values } => {
+                assert_eq!(rowid, None);
+                let mut row = [0; 2];
+                for (dst, mut val) in row.iter_mut().zip(values) {
+                    *dst = val.value().expect("unchanged value in insert").get_i64();
+                }
+                rows.push(row);
+                Ok(rows.len() as i64 - 1)
+            }
+            Change::Update {
+                old_rowid,
+                new_rowid,
+                values,
+            } => {
+                assert_eq!(old_rowid, new_rowid);
+                let row = &mut rows[old_rowid as usize];
+                for (dst, val) in row.iter_mut().zip(values) {
+                    match val {
+                        ColumnValue::Set(val) => *dst = val.get_i64(),
+                        ColumnValue::Unchanged => *dst = -*dst,
+                    }
+                }
+                Ok(new_rowid)
+            }
+            Change::Delete { rowid } => {
+                assert_eq!(rowid as usize, rows.len() - 1);
+                rows.pop();
+                Ok(0)
+            }
+        }
+    }
+}
+
+impl VTabCursor for Cursor<'_> {
+    fn filter(&mut self, _: i32, _: Option<&str>, _: &mut [&mut ValueRef]) -> Result<()> {
+        self.idx = 0;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<()> {
+        self.idx += 1;
+        Ok(())
+    }
+
+    fn eof(&mut self) -> bool {
+        self.idx >= self.rows.borrow().len()
+    }
+
+    fn column(&mut self, idx: usize, c: &ColumnContext) -> Result<()> {
+        if c.nochange() {
+            return Err(Error::NoChange);
+        }
+        c.set_result(self.rows.borrow()[self.idx][idx])
+    }
+
+    fn rowid(&mut self) -> Result<i64> {
+        Ok(self.idx as _)
+    }
+}
+
+#[test]
+fn change() -> Result<()> {
+    let conn = Database::open(":memory:")?;
+    let rows = RefCell::default();
+    conn.create_module("tbl", Table::module(), rows)?;
+    conn.execute("INSERT INTO tbl VALUES (1, 2), (3, 4), (5, 6)", ())?;
+    conn.execute("DELETE FROM tbl WHERE rowid = 2", ())?;
+    conn.execute("UPDATE tbl SET b = 10 WHERE rowid = 1", ())?;
+    let results: Vec<(i64, i64)> = conn
+        .query("SELECT a, b FROM tbl", ())?
+        .map(|r| Ok((r[0].get_i64(), r[1].get_i64())))
+        .collect()?;
+    // The update handler negates unchanged values to show that they were detected.
+    if cfg!(modern_sqlite) {
+        assert_eq!(results, vec![(1, 2), (-3, 10)]);
+    } else {
+        assert_eq!(results, vec![(1, 2), (3, 10)]);
+    }
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//! Test cases for CollectionVTab.
+use sqlite3_ext::{vtab::*, *};
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+#[derive(ToRow, Debug, PartialEq)]
+struct Person {
+    name: String,
+    age: i64,
+    email: Option<String>,
+}
+
+impl FromRow for Person {
+    fn from_row(values: &mut [&mut ValueRef]) -> Result<Self> {
+        Ok(Person {
+            name: values[0].get_str()?.to_owned(),
+            age: values[1].get_i64(),
+            email: match values[2].is_null() {
+                true => None,
+                false => Some(values[2].get_str()?.to_owned()),
+            },
+        })
+    }
+}
+
+fn person(name: &str, age: i64) -> Person {
+    Person {
+        name: name.to_owned(),
+        age,
+        email: None,
+    }
+}
+
+fn names(conn: &Connection, sql: &str) -> Result<Vec<String>> {
+    conn.query(sql, ())?
+        .map(|r| Ok(r[0].get_str()?.to_owned()))
+        .collect()
+}
+
+#[test]
+fn schema() {
+    assert_eq!(
+        Person::schema(),
+        "CREATE TABLE x ( \"name\" TEXT, \"age\" INTEGER, \"email\" TEXT )"
+    );
+}
+
+#[test]
+fn vec() -> Result<()> {
+    let conn = Database::open(":memory:")?;
+    let data = Rc::new(RefCell::new(vec![
+        person("Alice", 30),
+        person("Bob", 25),
+        person("Carol", 41),
+    ]));
+    conn.create_module("people", CollectionVTab::module(), data)?;
+    assert_eq!(
+        names(&conn, "SELECT name FROM people")?,
+        vec!["Alice", "Bob", "Carol"]
+    );
+    assert_eq!(
+        names(&conn, "SELECT name FROM people WHERE rowid = 1")?,
+        vec!["Bob"]
+    );
+    assert_eq!(
+        names(
+            &conn,
+            "SELECT name FROM people WHERE rowid >= 1 AND rowid < 2.5"
+        )?,
+        vec!["Bob", "Carol"]
+    );
+    assert_eq!(
+        names(&conn, "SELECT name FROM people WHERE rowid > 5")?,
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        names(&conn, "SELECT name FROM people WHERE rowid = 1.0")?,
+        vec!["Bob"]
+    );
+    assert_eq!(
+        names(&conn, "SELECT name FROM people WHERE rowid = 1.5")?,
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        names(&conn, "SELECT name FROM people WHERE rowid = NULL")?,
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        names(
+            &conn,
+            "SELECT name FROM people WHERE age > 26 ORDER BY rowid"
+        )?,
+        vec!["Alice", "Carol"]
+    );
+    Ok(())
+}
+
+#[test]
+fn vec_update() -> Result<()> {
+    let conn = Database::open(":memory:")?;
+    let data = Rc::new(RefCell::new(vec![person("Alice", 30)]));
+    conn.create_module("people", CollectionVTab::update_module(), data.clone())?;
+    let rowid = conn.insert("INSERT INTO people (name, age) VALUES ('Bob', 25)", ())?;
+    assert_eq!(rowid, 1);
+    conn.execute("UPDATE people SET age = age + 1 WHERE name = 'Alice'", ())?;
+    assert_eq!(*data.borrow(), vec![person("Alice", 31), person("Bob", 25)]);
+    conn.execute("DELETE FROM people WHERE rowid = 1", ())?;
+    assert_eq!(*data.borrow(), vec![person("Alice", 31)]);
+    assert!(conn
+        .execute("DELETE FROM people WHERE rowid = 5", ())
+        .is_ok());
+    data.borrow_mut().push(person("Carol", 41));
+    assert!(conn
+        .execute("DELETE FROM people WHERE rowid = 0", ())
+        .is_err());
+    assert!(conn.execute("DELETE FROM people", ()).is_err());
+    assert_eq!(data.borrow().len(), 2);
+    Ok(())
+}
+
+#[test]
+fn btree_map() -> Result<()> {
+    let conn = Database::open(":memory:")?;
+    let data: BTreeMap<u8, Person> = [
+        (10, person("Alice", 30)),
+        (20, person("Bob", 25)),
+        (30, person("Carol", 41)),
+    ]
+    .into_iter()
+    .collect();
+    let data = Rc::new(RefCell::new(data));
+    conn.create_module("people", CollectionVTab::update_module(), data.clone())?;
+    assert_eq!(
+        names(&conn, "SELECT name FROM people WHERE rowid > 10")?,
+        vec!["Bob", "Carol"]
+    );
+    assert_eq!(
+        names(
+            &conn,
+            "SELECT name FROM people WHERE rowid > -1000 AND rowid <= 1000"
+        )?,
+        vec!["Alice", "Bob", "Carol"]
+    );
+    assert_eq!(
+        names(&conn, "SELECT name FROM people WHERE rowid = 20")?,
+        vec!["Bob"]
+    );
+    let rowid = conn.insert("INSERT INTO people (name, age) VALUES ('Dave', 50)", ())?;
+    assert_eq!(rowid, 31);
+    conn.execute("UPDATE people SET rowid = 5 WHERE name = 'Carol'", ())?;
+    conn.execute("DELETE FROM people WHERE name = 'Alice'", ())?;
+    assert_eq!(
+        data.borrow().keys().copied().collect::<Vec<_>>(),
+        vec![5, 20, 31]
+    );
+    assert!(conn
+        .insert(
+            "INSERT INTO people (rowid, name, age) VALUES (20, 'Eve', 1)",
+            ()
+        )
+        .is_err());
+    assert!(conn
+        .insert(
+            "INSERT INTO people (rowid, name, age) VALUES (300, 'Eve', 1)",
+            ()
+        )
+        .is_err());
+    Ok(())
+}
@@ -0,0 +1,121 @@
+//! Test cases for GeneratorCursor.
+use sqlite3_ext::{vtab::*, *};
+
+#[derive(VTabSchema)]
+struct Row {
+    value: i64,
+    #[sqlite(hidden)]
+    stop: Option<i64>,
+}
+
+type MakeGenerator = fn(i64) -> Generator<'static, Row>;
+
+#[sqlite3_ext_vtab(EponymousModule)]
+struct Counter {
+    generator: MakeGenerator,
+}
+
+impl<'vtab> VTab<'vtab> for Counter {
+    type Aux = MakeGenerator;
+    type Cursor = GeneratorCursor<'vtab, Row>;
+
+    fn connect(_db: &VTabConnection, aux: &Self::Aux, _args: &[&str]) -> Result<(String, Self)> {
+        Ok((Row::DECLARATION.to_owned(), Counter { generator: *aux }))
+    }
+
+    fn best_index(&self, index_info: &mut IndexInfo) -> Result<()> {
+        for mut c in index_info.constraints() {
+            if c.column() == Row::COLUMN_STOP as i32 {
+                if !c.usable() || c.op() != ConstraintOp::Eq {
+                    return Err(SQLITE_CONSTRAINT);
+                }
+                c.set_argv_index(Some(0));
+                c.set_omit(true);
+                return Ok(());
+            }
+        }
+        Err(SQLITE_CONSTRAINT)
+    }
+
+    fn open(&'vtab self) -> Result<Self::Cursor> {
+        Ok(GeneratorCursor::new(|_, _, args| {
+            let stop = args[0].get_i64();
+            if stop < 0 {
+                return Err(Error::Module("stop must not be negative".to_owned()));
+            }
+            Ok((self.generator)(stop))
+        }))
+    }
+}
+
+fn from_fn(stop: i64) -> Generator<'static, Row> {
+    let mut value = 0;
+    GeneratorCursor::from_fn(move || {
+        value += 1;
+        Ok(if value <= stop {
+            Some(Row { value, stop: None })
+        } else {
+            None
+        })
+    })
+}
+
+fn from_iterator(stop: i64) -> Generator<'static, Row> {
+    GeneratorCursor::from_iterator((1..=stop).map(|value| Row { value, stop: None }))
+}
+
+fn failing(_stop: i64) -> Generator<'static, Row> {
+    let mut value = 0;
+    GeneratorCursor::from_fn(move || {
+        value += 1;
+        match value {
+            1 => Ok(Some(Row { value, stop: None })),
+            _ => Err(Error::Module("generator failed".to_owned())),
+        }
+    })
+}
+
+#[cfg(feature = "futures")]
+fn from_stream(stop: i64) -> Generator<'static, Row> {
+    GeneratorCursor::from_stream(futures::stream::iter(
+        (1..=stop).map(|value| Ok(Row { value, stop: None })),
+    ))
+}
+
+fn run(generator: MakeGenerator, stop: i64) -> Result<Vec<(i64, i64)>> {
+    let conn = Database::open(":memory:")?;
+    conn.create_module("counter", Counter::module(), generator)?;
+    let results = conn
+        .query("SELECT rowid, value FROM counter(?)", [stop])?
+        .map(|r| Ok((r[0].get_i64(), r[1].get_i64())))
+        .collect();
+    results
+}
+
+#[test]
+fn generator() -> Result<()> {
+    assert_eq!(run(from_fn, 3)?, vec![(1, 1), (2, 2), (3, 3)]);
+    assert_eq!(run(from_fn, 0)?, vec![]);
+    assert_eq!(run(from_iterator, 2)?, vec![(1, 1), (2, 2)]);
+    #[cfg(feature = "futures")]
+    assert_eq!(run(from_stream, 2)?, vec![(1, 1), (2, 2)]);
+    Ok(())
+}
+
+#[test]
+fn errors() {
+    assert_eq!(
+        run(from_fn, -1),
+        Err(Error::Sqlite(
+            ffi::SQLITE_ERROR,
+            Some("stop must not be negative".to_owned())
+        ))
+    );
+    assert_eq!(
+        run(failing, 1),
+        Err(Error::Sqlite(
+            ffi::SQLITE_ERROR,
+            Some("generator failed".to_owned())
+        ))
+    );
+}
@@ -1,5 +1,10 @@
+mod change;
+mod collection;
 mod errors;
 mod find_function;
+mod generator;
 mod index_info;
 mod module_types;
+mod schema;
 mod test_vtab;
+mod without_rowid;
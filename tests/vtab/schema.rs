@@ -0,0 +1,139 @@
+//! Test cases for derive(VTabSchema).
+use sqlite3_ext::{vtab::*, *};
+
+#[derive(VTabSchema, Default)]
+struct SeriesRow {
+    value: i64,
+    #[sqlite(hidden)]
+    start: i64,
+    #[sqlite(hidden, type = "INT")]
+    stop: i64,
+}
+
+#[derive(VTabSchema)]
+#[sqlite(without_rowid)]
+#[allow(dead_code)]
+struct KeyValue {
+    namespace: String,
+    #[sqlite(primary_key)]
+    key: String,
+    value: Option<Blob>,
+    r#type: Value,
+}
+
+#[derive(VTabSchema)]
+#[allow(dead_code)]
+struct Renamed {
+    #[sqlite(rename = "first name")]
+    first_name: String,
+}
+
+#[test]
+fn declaration() {
+    assert_eq!(
+        SeriesRow::DECLARATION,
+        "CREATE TABLE x ( \"value\" INTEGER, \"start\" INTEGER HIDDEN, \"stop\" INT HIDDEN )"
+    );
+    assert_eq!(SeriesRow::COLUMNS, &["value", "start", "stop"]);
+    assert_eq!(SeriesRow::schema(), SeriesRow::DECLARATION);
+    assert_eq!(
+        (
+            SeriesRow::COLUMN_VALUE,
+            SeriesRow::COLUMN_START,
+            SeriesRow::COLUMN_STOP
+        ),
+        (0, 1, 2)
+    );
+    assert_eq!(
+        KeyValue::DECLARATION,
+        "CREATE TABLE x ( \"namespace\" TEXT, \"key\" TEXT, \"value\" BLOB, \"type\", PRIMARY KEY (\"key\") ) WITHOUT ROWID"
+    );
+    assert_eq!(KeyValue::COLUMN_TYPE, 3);
+    assert_eq!(SeriesRow::PRIMARY_KEY, None);
+    assert_eq!(KeyValue::PRIMARY_KEY, Some(1));
+    assert_eq!(
+        Renamed::DECLARATION,
+        "CREATE TABLE x ( \"first name\" TEXT )"
+    );
+    assert_eq!(Renamed::COLUMNS, &["first name"]);
+    assert_eq!(Renamed::COLUMN_FIRST_NAME, 0);
+}
+
+#[sqlite3_ext_vtab(EponymousModule)]
+struct Series {}
+
+struct SeriesCursor {
+    row: SeriesRow,
+}
+
+impl VTab<'_> for Series {
+    type Aux = ();
+    type Cursor = SeriesCursor;
+
+    fn connect(_db: &VTabConnection, _aux: &(), _args: &[&str]) -> Result<(String, Self)> {
+        Ok((SeriesRow::DECLARATION.to_owned(), Series {}))
+    }
+
+    fn best_index(&self, index_info: &mut IndexInfo) -> Result<()> {
+        let mut idx = 0;
+        for mut c in index_info.constraints() {
+            if c.usable()
+                && c.op() == ConstraintOp::Eq
+                && (c.column() == SeriesRow::COLUMN_START as i32
+                    || c.column() == SeriesRow::COLUMN_STOP as i32)
+            {
+                c.set_argv_index(Some(c.column() as u32 - 1));
+                c.set_omit(true);
+                idx += 1;
+            }
+        }
+        if idx != 2 {
+            return Err(SQLITE_CONSTRAINT);
+        }
+        Ok(())
+    }
+
+    fn open(&self) -> Result<SeriesCursor> {
+        Ok(SeriesCursor {
+            row: SeriesRow::default(),
+        })
+    }
+}
+
+impl VTabCursor for SeriesCursor {
+    fn filter(&mut self, _: i32, _: Option<&str>, args: &mut [&mut ValueRef]) -> Result<()> {
+        self.row.start = args[0].get_i64();
+        self.row.stop = args[1].get_i64();
+        self.row.value = self.row.start;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<()> {
+        self.row.value += 1;
+        Ok(())
+    }
+
+    fn eof(&mut self) -> bool {
+        self.row.value > self.row.stop
+    }
+
+    fn column(&mut self, idx: usize, c: &ColumnContext) -> Result<()> {
+        c.set_row_field(&self.row, idx)
+    }
+
+    fn rowid(&mut self) -> Result<i64> {
+        Ok(self.row.value)
+    }
+}
+
+#[test]
+fn set_row_field() -> Result<()> {
+    let conn = Database::open(":memory:")?;
+    conn.create_module("series", Series::module(), ())?;
+    let results: Vec<(i64, i64)> = conn
+        .query("SELECT value, stop FROM series(3, 5)", ())?
+        .map(|r| Ok((r[0].get_i64(), r[1].get_i64())))
+        .collect()?;
+    assert_eq!(results, vec![(3, 5), (4, 5), (5, 5)]);
+    Ok(())
+}
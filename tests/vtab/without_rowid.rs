@@ -0,0 +1,217 @@
+//! Test cases for WITHOUT ROWID virtual tables.
+use sqlite3_ext::{vtab::*, *};
+use std::{cell::RefCell, collections::BTreeMap};
+
+#[derive(VTabSchema, Clone, Debug, PartialEq)]
+#[sqlite(without_rowid)]
+struct Setting {
+    #[sqlite(primary_key)]
+    key: String,
+    value: i64,
+}
+
+#[sqlite3_ext_vtab(EponymousModule, UpdateVTab)]
+struct Settings<'vtab> {
+    data: &'vtab RefCell<BTreeMap<String, i64>>,
+}
+
+struct SettingsCursor {
+    rows: std::vec::IntoIter<Setting>,
+    current: Option<Setting>,
+}
+
+impl<'vtab> VTab<'vtab> for Settings<'vtab> {
+    type Aux = RefCell<BTreeMap<String, i64>>;
+    type Cursor = SettingsCursor;
+
+    fn connect(
+        _db: &'vtab VTabConnection,
+        aux: &'vtab Self::Aux,
+        _args: &[&str],
+    ) -> Result<(String, Self)> {
+        Ok((Setting::DECLARATION.to_owned(), Settings { data: aux }))
+    }
+
+    fn best_index(&self, _index_info: &mut IndexInfo) -> Result<()> {
+        Ok(())
+    }
+
+    fn open(&self) -> Result<Self::Cursor> {
+        let rows: Vec<_> = self
+            .data
+            .borrow()
+            .iter()
+            .map(|(key, value)| Setting {
+                key: key.clone(),
+                value: *value,
+            })
+            .collect();
+        Ok(SettingsCursor {
+            rows: rows.into_iter(),
+            current: None,
+        })
+    }
+}
+
+impl<'vtab> UpdateVTab<'vtab> for Settings<'vtab> {
+    const PRIMARY_KEY: Option<usize> = Setting::PRIMARY_KEY;
+
+    fn update(&'vtab self, info: &mut ChangeInfo) -> Result<i64> {
+        let old_key = match info.old_primary_key() {
+            Some(k) => Some(k.try_get_str()?.to_owned()),
+            None => None,
+        };
+        let new_key = match info.new_primary_key() {
+            Some(k) => Some(k.try_get_str()?.to_owned()),
+            None => None,
+        };
+        let mut data = self.data.borrow_mut();
+        if let Some(k) = old_key {
+            data.remove(&k);
+        }
+        if let Some(k) = new_key {
+            let value = info.args()[1 + Setting::COLUMN_VALUE].get_i64();
+            data.insert(k, value);
+        }
+        Ok(0)
+    }
+}
+
+impl VTabCursor for SettingsCursor {
+    fn filter(&mut self, _: i32, _: Option<&str>, _: &mut [&mut ValueRef]) -> Result<()> {
+        self.current = self.rows.next();
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<()> {
+        self.current = self.rows.next();
+        Ok(())
+    }
+
+    fn eof(&mut self) -> bool {
+        self.current.is_none()
+    }
+
+    fn column(&mut self, idx: usize, c: &ColumnContext) -> Result<()> {
+        c.set_row_field(self.current.as_ref().unwrap(), idx)
+    }
+}
+
+#[derive(VTabSchema, Clone, Debug, PartialEq)]
+#[sqlite(without_rowid)]
+struct Edge {
+    #[sqlite(primary_key)]
+    src: i64,
+    #[sqlite(primary_key)]
+    dst: i64,
+}
+
+#[sqlite3_ext_vtab(EponymousModule)]
+struct Edges {}
+
+#[sqlite3_ext_vtab(EponymousModule, UpdateVTab)]
+struct WritableEdges {}
+
+struct EdgesCursor {
+    idx: i64,
+}
+
+impl VTab<'_> for Edges {
+    type Aux = ();
+    type Cursor = EdgesCursor;
+
+    fn connect(_db: &VTabConnection, _aux: &(), _args: &[&str]) -> Result<(String, Self)> {
+        Ok((Edge::DECLARATION.to_owned(), Edges {}))
+    }
+
+    fn best_index(&self, _index_info: &mut IndexInfo) -> Result<()> {
+        Ok(())
+    }
+
+    fn open(&self) -> Result<Self::Cursor> {
+        Ok(EdgesCursor { idx: 0 })
+    }
+}
+
+impl VTab<'_> for WritableEdges {
+    type Aux = ();
+    type Cursor = EdgesCursor;
+
+    fn connect(_db: &VTabConnection, _aux: &(), _args: &[&str]) -> Result<(String, Self)> {
+        Ok((Edge::DECLARATION.to_owned(), WritableEdges {}))
+    }
+
+    fn best_index(&self, _index_info: &mut IndexInfo) -> Result<()> {
+        Ok(())
+    }
+
+    fn open(&self) -> Result<Self::Cursor> {
+        Ok(EdgesCursor { idx: 0 })
+    }
+}
+
+impl UpdateVTab<'_> for WritableEdges {
+    fn update(&self, _info: &mut ChangeInfo) -> Result<i64> {
+        Ok(0)
+    }
+}
+
+impl VTabCursor for EdgesCursor {
+    fn filter(&mut self, _: i32, _: Option<&str>, _: &mut [&mut ValueRef]) -> Result<()> {
+        self.idx = 0;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<()> {
+        self.idx += 1;
+        Ok(())
+    }
+
+    fn eof(&mut self) -> bool {
+        self.idx >= 3
+    }
+
+    fn column(&mut self, idx: usize, c: &ColumnContext) -> Result<()> {
+        let edge = Edge {
+            src: self.idx,
+            dst: self.idx + 1,
+        };
+        c.set_row_field(&edge, idx)
+    }
+}
+
+#[test]
+fn without_rowid() -> Result<()> {
+    let conn = Database::open(":memory:")?;
+    conn.create_module("settings", Settings::module(), RefCell::default())?;
+    conn.execute(
+        "INSERT INTO settings VALUES ('a', 1), ('b', 2), ('c', 3)",
+        (),
+    )?;
+    conn.execute("UPDATE settings SET value = value * 10 WHERE key = 'b'", ())?;
+    conn.execute("UPDATE settings SET key = 'd' WHERE key = 'c'", ())?;
+    conn.execute("DELETE FROM settings WHERE key = 'a'", ())?;
+    let results: Vec<(String, i64)> = conn
+        .query("SELECT key, value FROM settings", ())?
+        .map(|r| Ok((r[0].get_str()?.to_owned(), r[1].get_i64())))
+        .collect()?;
+    assert_eq!(results, vec![("b".to_owned(), 20), ("d".to_owned(), 3)]);
+    Ok(())
+}
+
+#[test]
+fn multi_column_key() -> Result<()> {
+    assert_eq!(Edge::PRIMARY_KEY, None);
+    let conn = Database::open(":memory:")?;
+    conn.create_module("edges", Edges::module(), ())?;
+    let results: Vec<(i64, i64)> = conn
+        .query("SELECT src, dst FROM edges", ())?
+        .map(|r| Ok((r[0].get_i64(), r[1].get_i64())))
+        .collect()?;
+    assert_eq!(results, vec![(0, 1), (1, 2), (2, 3)]);
+
+    // SQLite only allows writable WITHOUT ROWID tables with a single-column key.
+    conn.create_module("writable_edges", WritableEdges::module(), ())?;
+    assert!(conn.query("SELECT * FROM writable_edges", ()).is_err());
+    Ok(())
+}